@@ -0,0 +1,550 @@
+//! Backward DRAT checking.
+//!
+//! The proof is first replayed forward up to the first empty clause, and then backward from the
+//! empty clause. Going backward, each lemma is removed from the clause database and, if it was
+//! used to justify a lemma checked so far, checked against the clauses that precede it.
+//!
+//! Deletions of unit clauses are ignored, as most DRAT checkers do.
+
+use crate::{prelude::*, Assignment};
+
+/// A proof step.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Lemma addition.
+    Add(Vec<Lit>),
+    /// Clause deletion.
+    Delete(Vec<Lit>),
+}
+
+/// Proof steps with their position in the proof.
+///
+/// Positions are line numbers for textual proofs, and step indices (starting at `1`) for binary
+/// ones.
+#[derive(Debug, Clone)]
+pub struct Steps {
+    pub steps: Vec<(usize, Step)>,
+    pub binary: bool,
+}
+impl Steps {
+    /// Describes a position in the proof.
+    fn pos(&self, pos: usize) -> String {
+        if self.binary {
+            format!("step {}", pos)
+        } else {
+            format!("line {}", pos)
+        }
+    }
+}
+
+/// True if a proof looks like a binary DRAT proof.
+///
+/// Textual proofs only contain digits, `-`, `d`, whitespaces, and letters (in comments). This
+/// check is the one `drat-trim` uses, it only looks at the first few bytes of the proof.
+pub fn is_binary(proof: &[u8]) -> bool {
+    proof
+        .iter()
+        .take(10)
+        .any(|b| !(b.is_ascii_digit() || b.is_ascii_alphabetic() || b" \n\r-".contains(b)))
+}
+
+/// Parses a DRAT proof, textual or binary.
+pub fn parse(proof: &[u8]) -> Res<Steps> {
+    if is_binary(proof) {
+        parse_binary(proof)
+    } else {
+        parse_text(proof)
+    }
+}
+
+/// Parses a textual DRAT proof.
+pub fn parse_text(proof: &[u8]) -> Res<Steps> {
+    use crate::Token;
+
+    let mut steps = Vec::new();
+    let mut lits = Vec::with_capacity(7);
+    // Line of the first token of the current step, and whether it's a deletion.
+    let mut current: Option<(usize, bool)> = None;
+
+    for token in crate::tokens(proof) {
+        let (line, token) = token.chain_err(|| "while parsing DRAT proof")?;
+        match (token, current) {
+            (Token::Delete, None) => current = Some((line, true)),
+            (Token::Delete, Some(_)) => {
+                bail!(
+                    "unexpected deletion marker `d` in a clause on line {}",
+                    line
+                )
+            }
+            (Token::Int(0), _) => {
+                let (line, delete) = current.take().unwrap_or((line, false));
+                let lits = std::mem::replace(&mut lits, Vec::with_capacity(7));
+                steps.push((
+                    line,
+                    if delete {
+                        Step::Delete(lits)
+                    } else {
+                        Step::Add(lits)
+                    },
+                ))
+            }
+            (Token::Int(int), _) => {
+                if current.is_none() {
+                    current = Some((line, false))
+                }
+                lits.push(crate::lit_of_int(int))
+            }
+        }
+    }
+    if let Some((line, _)) = current {
+        bail!("unterminated clause starting on line {}", line)
+    }
+
+    Ok(Steps {
+        steps,
+        binary: false,
+    })
+}
+
+/// Parses a binary DRAT proof.
+pub fn parse_binary(proof: &[u8]) -> Res<Steps> {
+    let mut steps = Vec::new();
+    let mut bytes = proof.iter().cloned();
+
+    while let Some(marker) = bytes.next() {
+        let step = steps.len() + 1;
+        let delete = match marker {
+            b'a' => false,
+            b'd' => true,
+            _ => bail!(
+                "expected `a` or `d` at the start of step {}, got `{:#x}`",
+                step,
+                marker
+            ),
+        };
+        let mut lits = Vec::with_capacity(7);
+        loop {
+            let mut code = 0u64;
+            let mut shift = 0;
+            loop {
+                let byte = match bytes.next() {
+                    Some(byte) => byte,
+                    None => bail!("unterminated clause at step {}", step),
+                };
+                if shift > 56 {
+                    bail!("illegal literal encoding at step {}", step)
+                }
+                code |= u64::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            if code == 0 {
+                break;
+            } else if code == 1 {
+                bail!("illegal literal encoding at step {}", step)
+            }
+            lits.push(Lit::new((code >> 1) as usize, code & 1 == 1))
+        }
+        steps.push((
+            step,
+            if delete {
+                Step::Delete(lits)
+            } else {
+                Step::Add(lits)
+            },
+        ))
+    }
+
+    Ok(Steps {
+        steps,
+        binary: true,
+    })
+}
+
+/// Checks a DRAT proof, textual or binary, for a CNF.
+pub fn check(cnf: &Cnf<Lit>, proof: &[u8]) -> Res<Summary> {
+    let steps = parse(proof)?;
+    let mut db = Db::default();
+
+    for clause in cnf.iter() {
+        if clause.is_empty() {
+            // Nothing to check.
+            return Ok(Summary {
+                core: Some(1),
+                ..Summary::default()
+            });
+        }
+        let _ = db.add(&clause.iter().cloned().collect::<Vec<_>>());
+    }
+
+    /// Proof events, with clause identifiers instead of clauses.
+    enum Event {
+        Add {
+            id: usize,
+            pos: usize,
+            pivot: Option<Lit>,
+        },
+        Delete {
+            id: usize,
+        },
+    }
+
+    // Forward pass, up to the empty clause.
+    let mut events = Vec::with_capacity(steps.steps.len());
+    let mut lemmas = 0;
+    let mut refuted = false;
+    for (pos, step) in steps.steps.iter() {
+        match step {
+            Step::Add(lits) => {
+                lemmas += 1;
+                let id = db.add(lits);
+                events.push(Event::Add {
+                    id,
+                    pos: *pos,
+                    pivot: lits.first().cloned(),
+                });
+                if lits.is_empty() {
+                    refuted = true;
+                    break;
+                }
+            }
+            Step::Delete(lits) => {
+                if crate::normalized(lits).len() == 1 {
+                    log::debug!("ignoring deletion of unit clause on {}", steps.pos(*pos));
+                    continue;
+                }
+                match db.remove(lits) {
+                    Some(id) => events.push(Event::Delete { id }),
+                    None => {
+                        log::warn!("ignoring deletion of unknown clause on {}", steps.pos(*pos))
+                    }
+                }
+            }
+        }
+    }
+    if !refuted {
+        bail!("proof does not derive the empty clause")
+    }
+
+    // Backward pass.
+    let mut checked = 0;
+    if let Some(Event::Add { id, .. }) = events.last() {
+        db.clauses[*id].marked = true
+    }
+    for event in events.iter().rev() {
+        match *event {
+            Event::Delete { id } => db.clauses[id].active = true,
+            Event::Add { id, pos, pivot } => {
+                db.clauses[id].active = false;
+                if !db.clauses[id].marked {
+                    continue;
+                }
+                checked += 1;
+                let lits = db.clauses[id].lits.clone();
+                if db.rup(&lits) || pivot.map(|pivot| db.rat(&lits, pivot)).unwrap_or(false) {
+                    continue;
+                }
+                bail!(
+                    "lemma `{}` on {} is neither RUP nor RAT",
+                    lits.iter().fold(String::new(), |mut acc, lit| {
+                        acc.push_str(&lit.to_string());
+                        acc.push(' ');
+                        acc
+                    }) + "0",
+                    steps.pos(pos),
+                )
+            }
+        }
+    }
+
+    Ok(Summary {
+        lemmas,
+        checked,
+        core: Some(db.clauses[0..cnf.len()].iter().filter(|c| c.marked).count()),
+    })
+}
+
+/// A clause of the database.
+#[derive(Debug, Clone)]
+struct Clause {
+    /// Literals, the first two are watched if the clause is not a tautology.
+    lits: Vec<Lit>,
+    /// False if the clause is deleted.
+    active: bool,
+    /// True if the clause is needed by the proof.
+    marked: bool,
+    /// True if the clause contains a literal and its negation.
+    tautology: bool,
+}
+
+/// Clause database with two watched literals unit propagation.
+///
+/// All propagations start from an empty assignment, which means any two literals of a clause are
+/// valid watches when propagation starts. In particular, (de)activating clauses does not require
+/// updating the watches.
+#[derive(Debug, Clone, Default)]
+struct Db {
+    clauses: Vec<Clause>,
+    /// Maps literal codes to the clauses watching them.
+    watches: Vec<Vec<usize>>,
+    /// Unit clauses.
+    units: Vec<usize>,
+    /// Maps normalized clauses to the active clauses they correspond to.
+    index: Map<Vec<Lit>, Vec<usize>>,
+    assignment: Assignment,
+    /// Maps variable indices to the clause that propagated them, if any.
+    reasons: Vec<Option<usize>>,
+    /// Maps variable indices to a flag used during conflict analysis.
+    seen: Vec<bool>,
+    /// Index of the next literal to propagate in the trail.
+    qhead: usize,
+}
+impl Db {
+    /// Adds a clause, yields its identifier.
+    fn add(&mut self, lits: &[Lit]) -> usize {
+        let id = self.clauses.len();
+        let lits = crate::normalized(lits);
+        let tautology = crate::is_tautology(&lits);
+
+        for lit in &lits {
+            let code = crate::lit_code(*lit) | 1;
+            if self.watches.len() <= code {
+                self.watches.resize(code + 1, Vec::new())
+            }
+            if self.reasons.len() <= lit.idx() {
+                self.reasons.resize(lit.idx() + 1, None);
+                self.seen.resize(lit.idx() + 1, false);
+            }
+            self.assignment.reserve(*lit)
+        }
+        if !tautology {
+            match lits.len() {
+                0 => (),
+                1 => self.units.push(id),
+                _ => {
+                    self.watches[crate::lit_code(lits[0])].push(id);
+                    self.watches[crate::lit_code(lits[1])].push(id);
+                }
+            }
+        }
+
+        self.index.entry(lits.clone()).or_default().push(id);
+        self.clauses.push(Clause {
+            lits,
+            active: true,
+            marked: false,
+            tautology,
+        });
+        id
+    }
+
+    /// Deactivates a clause, yields its identifier if it was found.
+    fn remove(&mut self, lits: &[Lit]) -> Option<usize> {
+        let id = self.index.get_mut(&crate::normalized(lits))?.pop()?;
+        self.clauses[id].active = false;
+        Some(id)
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        self.assignment.assign(lit);
+        self.reasons[lit.idx()] = reason
+    }
+
+    /// Unit propagation, yields the conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.assignment.trail.len() {
+            let false_lit = self.assignment.trail[self.qhead].negate();
+            self.qhead += 1;
+            let code = crate::lit_code(false_lit);
+            let mut watches = std::mem::take(&mut self.watches[code]);
+            let mut conflict = None;
+
+            let mut idx = 0;
+            'watches: while idx < watches.len() {
+                let id = watches[idx];
+                let clause = &mut self.clauses[id];
+                if !clause.active {
+                    idx += 1;
+                    continue 'watches;
+                }
+                if clause.lits[0] == false_lit {
+                    clause.lits.swap(0, 1)
+                }
+                debug_assert_eq!(clause.lits[1], false_lit);
+                let first = clause.lits[0];
+                if self.assignment.value(first) == Some(true) {
+                    idx += 1;
+                    continue 'watches;
+                }
+                for new_idx in 2..clause.lits.len() {
+                    if self.assignment.value(clause.lits[new_idx]) != Some(false) {
+                        clause.lits.swap(1, new_idx);
+                        self.watches[crate::lit_code(clause.lits[1])].push(id);
+                        let _ = watches.swap_remove(idx);
+                        continue 'watches;
+                    }
+                }
+                if self.assignment.value(first) == Some(false) {
+                    conflict = Some(id);
+                    break 'watches;
+                }
+                self.assign(first, Some(id));
+                idx += 1;
+            }
+
+            self.watches[code] = watches;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Marks all clauses involved in deriving the literals of a clause, including the clause.
+    fn analyze(&mut self, id: usize) {
+        let mut todo = vec![id];
+        while let Some(id) = todo.pop() {
+            self.clauses[id].marked = true;
+            for lit in &self.clauses[id].lits {
+                let seen = &mut self.seen[lit.idx()];
+                if !*seen {
+                    *seen = true;
+                    if let Some(reason) = self.reasons[lit.idx()] {
+                        todo.push(reason)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that a clause is a *reverse unit propagation* (RUP) consequence of the active
+    /// clauses.
+    ///
+    /// On success, marks the clauses involved.
+    fn rup(&mut self, lits: &[Lit]) -> bool {
+        self.assignment.backtrack(0);
+        self.qhead = 0;
+
+        let conflict = self.rup_conflict(lits);
+        match conflict {
+            Some(Ok(id)) => self.analyze(id),
+            Some(Err(lit)) => {
+                if let Some(reason) = self.reasons[lit.idx()] {
+                    self.analyze(reason)
+                }
+            }
+            None => (),
+        }
+        for lit in &self.assignment.trail {
+            self.seen[lit.idx()] = false
+        }
+        self.assignment.backtrack(0);
+        conflict.is_some()
+    }
+
+    /// Propagates the unit clauses and the negation of `lits`.
+    ///
+    /// Yields the conflicting clause if any, or a literal of `lits` that's true after propagating
+    /// the unit clauses.
+    fn rup_conflict(&mut self, lits: &[Lit]) -> Option<Result<usize, Lit>> {
+        for idx in 0..self.units.len() {
+            let id = self.units[idx];
+            if !self.clauses[id].active {
+                continue;
+            }
+            let lit = self.clauses[id].lits[0];
+            match self.assignment.value(lit) {
+                Some(true) => (),
+                Some(false) => return Some(Ok(id)),
+                None => self.assign(lit, Some(id)),
+            }
+        }
+        for lit in lits {
+            match self.assignment.value(*lit) {
+                Some(true) => return Some(Err(*lit)),
+                Some(false) => (),
+                None => self.assign(lit.negate(), None),
+            }
+        }
+        self.propagate().map(Ok)
+    }
+
+    /// Checks that a clause is a *resolution asymmetric tautology* (RAT) on `pivot` w.r.t. the
+    /// active clauses.
+    ///
+    /// On success, marks the clauses involved.
+    fn rat(&mut self, lits: &[Lit], pivot: Lit) -> bool {
+        let npivot = pivot.negate();
+        let candidates: Vec<usize> = self
+            .clauses
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.active && !c.tautology && c.lits.contains(&npivot))
+            .map(|(id, _)| id)
+            .collect();
+        for id in candidates {
+            let mut resolvent = lits.to_vec();
+            resolvent.extend(self.clauses[id].lits.iter().filter(|lit| **lit != npivot));
+            if !self.rup(&resolvent) {
+                return false;
+            }
+            self.clauses[id].marked = true
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf_of;
+
+    /// All the clauses over two variables.
+    fn two_vars() -> Cnf<Lit> {
+        cnf_of(&[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]])
+    }
+
+    #[test]
+    fn rup() {
+        let summary = check(&two_vars(), b"2 0\n0\n").unwrap();
+        assert_eq!(summary.lemmas, 2);
+        assert_eq!(summary.checked, 2);
+        assert_eq!(summary.core, Some(4));
+    }
+
+    #[test]
+    fn rat() {
+        // `1` is not RUP, but it is RAT on `1` since its resolvent `1 2` with `-1 2` is RUP.
+        let cnf = cnf_of(&[&[-1, 2], &[2, 3], &[2, -3], &[-2, 4], &[-2, -4]]);
+        let summary = check(&cnf, b"1 0\n0\n").unwrap();
+        assert_eq!(summary.checked, 2);
+    }
+
+    #[test]
+    fn binary() {
+        // `a 2 0 a 0`, literal `2` is encoded as `2 · 2 = 4`.
+        let proof = b"a\x04\x00a\x00";
+        assert!(is_binary(proof));
+        let summary = check(&two_vars(), proof).unwrap();
+        assert_eq!(summary.checked, 2);
+    }
+
+    #[test]
+    fn neither_rup_nor_rat() {
+        let cnf = cnf_of(&[&[1, 2], &[-1, -2]]);
+        let e = check(&cnf, b"1 0\n2 0\n0\n").unwrap_err();
+        assert!(
+            crate::error_chain(&e).contains("is neither RUP nor RAT"),
+            "{}",
+            crate::error_chain(&e)
+        );
+    }
+
+    #[test]
+    fn no_empty_clause() {
+        let e = check(&two_vars(), b"2 0\n").unwrap_err();
+        assert!(crate::error_chain(&e).contains("does not derive the empty clause"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Proof checker, verifies DRAT and LRAT proofs of unsatisfiability for CNFs in the SAT-comp format.
+//!
+//! DRAT proofs, textual or binary, are checked *backwards* by [`drat`]: only the lemmas that are
+//! actually needed to derive the empty clause are verified. LRAT proofs embed the unit propagation
+//! steps justifying each lemma, [`lrat`] checks them forward by replaying these steps.
+
+pub mod prelude {
+    pub use front::prelude::*;
+
+    pub use crate::{Format, Summary};
+}
+
+pub mod drat;
+pub mod lrat;
+
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// Proof formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// DRAT, textual or binary.
+    Drat,
+    /// LRAT, textual.
+    Lrat,
+}
+implem! {
+    for Format {
+        Display {
+            |&self, fmt| match self {
+                Self::Drat => "DRAT".fmt(fmt),
+                Self::Lrat => "LRAT".fmt(fmt),
+            }
+        }
+    }
+}
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drat" => Some(Self::Drat),
+            "lrat" => Some(Self::Lrat),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a proof file from its extension, defaults to DRAT.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if "lrat" == ext => Self::Lrat,
+            _ => Self::Drat,
+        }
+    }
+}
+
+/// Information about a successful proof check.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    /// Number of lemmas in the proof, up to the empty clause.
+    pub lemmas: usize,
+    /// Number of lemmas that were actually checked.
+    pub checked: usize,
+    /// Number of clauses of the original formula used by the proof, if known.
+    pub core: Option<usize>,
+}
+
+/// Checks a proof stored in a file for a CNF.
+pub fn check_file(cnf: &Cnf<Lit>, proof: impl AsRef<Path>, format: Format) -> Res<Summary> {
+    let proof = proof.as_ref();
+    let bytes = std::fs::read(proof)
+        .chain_err(|| format!("while reading proof file `{}`", proof.display()))?;
+    check(cnf, &bytes, format)
+}
+
+/// Checks a proof for a CNF.
+pub fn check(cnf: &Cnf<Lit>, proof: &[u8], format: Format) -> Res<Summary> {
+    match format {
+        Format::Drat => drat::check(cnf, proof),
+        Format::Lrat => lrat::check(cnf, proof),
+    }
+}
+
+/// Parses the (signed) integers of a textual proof, reports the line each token comes from.
+///
+/// Lines starting with `c` are comments. Besides integers, the only token allowed is `d`.
+fn tokens(proof: &[u8]) -> impl Iterator<Item = Res<(usize, Token)>> + '_ {
+    proof
+        .split(|b| *b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| line.first() != Some(&b'c'))
+        .flat_map(|(idx, line)| {
+            line.split(|b| b.is_ascii_whitespace())
+                .filter(|token| !token.is_empty())
+                .map(move |token| {
+                    Token::parse(token)
+                        .map(|token| (idx + 1, token))
+                        .chain_err(|| format!("on line {}", idx + 1))
+                })
+        })
+}
+
+/// Token of a textual proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// Deletion marker.
+    Delete,
+    /// Integer.
+    Int(i64),
+}
+impl Token {
+    fn parse(token: &[u8]) -> Res<Self> {
+        if token == b"d" {
+            return Ok(Self::Delete);
+        }
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .map(Self::Int)
+            .ok_or_else(|| {
+                format!(
+                    "expected integer or `d`, got `{}`",
+                    String::from_utf8_lossy(token)
+                )
+                .into()
+            })
+    }
+}
+
+/// Turns a non-zero DIMACS integer into a literal.
+fn lit_of_int(int: i64) -> Lit {
+    debug_assert!(int != 0);
+    Lit::new(int.unsigned_abs() as usize, int < 0)
+}
+
+/// Index of a literal in vectors indexed by literals.
+fn lit_code(lit: Lit) -> usize {
+    (lit.idx() << 1) | (lit.is_neg() as usize)
+}
+
+/// Clause as a sorted vector of literals without duplicates.
+fn normalized(lits: &[Lit]) -> Vec<Lit> {
+    let mut lits = lits.to_vec();
+    lits.sort();
+    lits.dedup();
+    lits
+}
+
+/// True if a clause contains a literal and its negation.
+fn is_tautology(lits: &[Lit]) -> bool {
+    let lits = normalized(lits);
+    lits.windows(2).any(|pair| pair[0].idx() == pair[1].idx())
+}
+
+/// Assignment of the variables of a formula.
+#[derive(Debug, Clone, Default)]
+struct Assignment {
+    /// Maps variable indices to their value, if any.
+    values: Vec<Option<bool>>,
+    /// Literals assigned, in order.
+    trail: Vec<Lit>,
+}
+impl Assignment {
+    fn reserve(&mut self, lit: Lit) {
+        if self.values.len() <= lit.idx() {
+            self.values.resize(lit.idx() + 1, None)
+        }
+    }
+    /// Value of a literal, if any.
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values
+            .get(lit.idx())
+            .cloned()
+            .flatten()
+            .map(|val| val != lit.is_neg())
+    }
+    /// Assigns a literal to true, must be unassigned.
+    fn assign(&mut self, lit: Lit) {
+        self.reserve(lit);
+        debug_assert!(self.values[lit.idx()].is_none());
+        self.values[lit.idx()] = Some(!lit.is_neg());
+        self.trail.push(lit)
+    }
+    /// Unassigns all literals assigned after the first `len` ones.
+    fn backtrack(&mut self, len: usize) {
+        for lit in self.trail.drain(len..) {
+            self.values[lit.idx()] = None
+        }
+    }
+}
+
+/// CNF from DIMACS integers.
+#[cfg(test)]
+fn cnf_of(clauses: &[&[i64]]) -> Cnf<Lit> {
+    Cnf::new(
+        clauses
+            .iter()
+            .map(|lits| Clause::new(lits.iter().cloned().map(lit_of_int).collect()))
+            .collect(),
+    )
+}
+
+/// Error message, including the errors it was chained to.
+#[cfg(test)]
+fn error_chain(e: &err::Error) -> String {
+    e.iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(": ")
+}
@@ -0,0 +1,316 @@
+//! Forward LRAT checking.
+//!
+//! Each lemma comes with *hints*: the identifiers of the clauses that become unit (and then
+//! conflicting) when propagating the negation of the lemma. Checking a lemma amounts to replaying
+//! these propagations, RAT lemmas have additional hints for each clause containing the negation of
+//! their pivot (first literal).
+//!
+//! The clauses of the original formula are identified by their index, starting at `1`.
+
+use crate::{prelude::*, Assignment, Token};
+
+/// A proof step.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Lemma addition.
+    Add {
+        id: u64,
+        lits: Vec<Lit>,
+        hints: Vec<i64>,
+    },
+    /// Clause deletions.
+    Delete(Vec<u64>),
+}
+
+/// Parses a textual LRAT proof, yields each step with the line it starts on.
+pub fn parse(proof: &[u8]) -> Res<Vec<(usize, Step)>> {
+    let tokens = crate::tokens(proof)
+        .collect::<Res<Vec<_>>>()
+        .chain_err(|| "while parsing LRAT proof")?;
+    let mut tokens = tokens.into_iter().peekable();
+    let mut steps = Vec::new();
+
+    /// Parses integers up to the next `0`.
+    fn ints(
+        tokens: &mut impl Iterator<Item = (usize, Token)>,
+        start: usize,
+        mut action: impl FnMut(i64),
+    ) -> Res<()> {
+        loop {
+            match tokens.next() {
+                Some((_, Token::Int(0))) => return Ok(()),
+                Some((_, Token::Int(int))) => action(int),
+                Some((line, Token::Delete)) => {
+                    bail!("unexpected deletion marker `d` on line {}", line)
+                }
+                None => bail!("unterminated step starting on line {}", start),
+            }
+        }
+    }
+
+    while let Some((line, token)) = tokens.next() {
+        let id = match token {
+            Token::Int(id) if id > 0 => id as u64,
+            _ => bail!("expected clause identifier on line {}", line),
+        };
+        if let Some((_, Token::Delete)) = tokens.peek() {
+            let _ = tokens.next();
+            let mut ids = Vec::new();
+            ints(&mut tokens, line, |id| ids.push(id))?;
+            if let Some(id) = ids.iter().find(|id| **id <= 0) {
+                bail!("illegal clause identifier {} on line {}", id, line)
+            }
+            steps.push((
+                line,
+                Step::Delete(ids.into_iter().map(|id| id as u64).collect()),
+            ))
+        } else {
+            let mut lits = Vec::with_capacity(7);
+            ints(&mut tokens, line, |int| lits.push(crate::lit_of_int(int)))?;
+            let mut hints = Vec::with_capacity(7);
+            ints(&mut tokens, line, |int| hints.push(int))?;
+            steps.push((line, Step::Add { id, lits, hints }))
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Checks a textual LRAT proof for a CNF.
+pub fn check(cnf: &Cnf<Lit>, proof: &[u8]) -> Res<Summary> {
+    let steps = parse(proof)?;
+
+    let mut clauses: Map<u64, Vec<Lit>> = Map::with_capacity(cnf.len() + steps.len());
+    for (idx, clause) in cnf.iter().enumerate() {
+        if clause.is_empty() {
+            // Nothing to check.
+            return Ok(Summary::default());
+        }
+        let _ = clauses.insert(idx as u64 + 1, clause.iter().cloned().collect());
+    }
+
+    let mut assignment = Assignment::default();
+    let mut last_id = cnf.len() as u64;
+    let mut lemmas = 0;
+
+    for (line, step) in steps {
+        match step {
+            Step::Delete(ids) => {
+                for id in ids {
+                    if clauses.remove(&id).is_none() {
+                        log::warn!(
+                            "ignoring deletion of unknown clause {} on line {}",
+                            id,
+                            line
+                        )
+                    }
+                }
+            }
+            Step::Add { id, lits, hints } => {
+                if id <= last_id {
+                    bail!(
+                        "illegal clause identifier {} on line {}, identifiers must increase",
+                        id,
+                        line
+                    )
+                }
+                last_id = id;
+                lemmas += 1;
+                check_lemma(&clauses, &mut assignment, &lits, &hints)
+                    .chain_err(|| format!("while checking lemma {} on line {}", id, line))?;
+                if lits.is_empty() {
+                    return Ok(Summary {
+                        lemmas,
+                        checked: lemmas,
+                        core: None,
+                    });
+                }
+                let _ = clauses.insert(id, lits);
+            }
+        }
+    }
+
+    bail!("proof does not derive the empty clause")
+}
+
+/// Checks a lemma given its hints.
+fn check_lemma(
+    clauses: &Map<u64, Vec<Lit>>,
+    assignment: &mut Assignment,
+    lits: &[Lit],
+    hints: &[i64],
+) -> Res<()> {
+    assignment.backtrack(0);
+    for lit in lits {
+        match assignment.value(*lit) {
+            // Lemma is a tautology.
+            Some(true) => return Ok(()),
+            Some(false) => (),
+            None => assignment.assign(lit.negate()),
+        }
+    }
+
+    // RUP hints.
+    let mut hints = hints.iter().cloned().peekable();
+    while let Some(hint) = hints.next_if(|hint| *hint > 0) {
+        if unit_step(clauses, assignment, hint as u64)? {
+            return Ok(());
+        }
+    }
+
+    // RAT hints, one group for each clause containing the negation of the pivot.
+    let pivot = match lits.first() {
+        Some(pivot) => pivot,
+        None => bail!("hints do not lead to a conflict"),
+    };
+    let mut groups: Map<u64, Vec<u64>> = Map::new();
+    let mut current = None;
+    for hint in hints {
+        if hint < 0 {
+            current = Some(groups.entry(hint.unsigned_abs()).or_default())
+        } else if let Some(group) = current.as_mut() {
+            group.push(hint as u64)
+        }
+    }
+
+    let npivot = pivot.negate();
+    let base = assignment.trail.len();
+    let mut candidates: Vec<_> = clauses
+        .iter()
+        .filter(|(_, clause)| clause.contains(&npivot))
+        .collect();
+    candidates.sort_by_key(|(id, _)| **id);
+    for (id, clause) in candidates {
+        let mut conflict = false;
+        for lit in clause.iter().filter(|lit| **lit != npivot) {
+            match assignment.value(*lit) {
+                Some(true) => {
+                    conflict = true;
+                    break;
+                }
+                Some(false) => (),
+                None => assignment.assign(lit.negate()),
+            }
+        }
+        if !conflict {
+            let group = match groups.get(id) {
+                Some(group) => group,
+                None => bail!("missing RAT hints for clause {}", id),
+            };
+            for hint in group {
+                if unit_step(clauses, assignment, *hint)? {
+                    conflict = true;
+                    break;
+                }
+            }
+        }
+        assignment.backtrack(base);
+        if !conflict {
+            bail!("RAT hints for clause {} do not lead to a conflict", id)
+        }
+    }
+
+    Ok(())
+}
+
+/// Propagates a hint clause, yields `true` if it is conflicting.
+///
+/// Fails if the clause is neither unit nor conflicting.
+fn unit_step(clauses: &Map<u64, Vec<Lit>>, assignment: &mut Assignment, id: u64) -> Res<bool> {
+    let clause = match clauses.get(&id) {
+        Some(clause) => clause,
+        None => bail!("unknown clause {} in hints", id),
+    };
+    let mut unit = None;
+    for lit in clause {
+        match assignment.value(*lit) {
+            Some(true) => bail!("hint clause {} is satisfied", id),
+            Some(false) => (),
+            None if unit.is_none() || unit == Some(lit) => unit = Some(lit),
+            None => bail!("hint clause {} is not unit", id),
+        }
+    }
+    match unit {
+        None => Ok(true),
+        Some(lit) => {
+            assignment.assign(*lit);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf_of;
+
+    /// All the clauses over two variables, identified by `1` to `4`.
+    fn two_vars() -> Cnf<Lit> {
+        cnf_of(&[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]])
+    }
+
+    /// Fails if checking succeeds or its error does not mention `msg`.
+    fn assert_fails(cnf: &Cnf<Lit>, proof: &[u8], msg: &str) {
+        match check(cnf, proof) {
+            Ok(_) => panic!("expected an error mentioning `{}`", msg),
+            Err(e) => {
+                let e = crate::error_chain(&e);
+                assert!(e.contains(msg), "expected `{}`, got `{}`", msg, e)
+            }
+        }
+    }
+
+    #[test]
+    fn rup() {
+        let summary = check(&two_vars(), b"5 2 0 1 2 0\n6 0 5 3 4 0\n").unwrap();
+        assert_eq!(summary.lemmas, 2);
+    }
+
+    #[test]
+    fn rat() {
+        // `1` is RAT on `1`: the resolvent `1 2` with clause `1` propagates `3` with clause `2`,
+        // which falsifies clause `3`.
+        let cnf = cnf_of(&[&[-1, 2], &[2, 3], &[2, -3], &[-2, 4], &[-2, -4]]);
+        let summary = check(&cnf, b"6 1 0 -1 2 3 0\n7 0 6 1 4 5 0\n").unwrap();
+        assert_eq!(summary.lemmas, 2);
+    }
+
+    #[test]
+    fn bad_hints() {
+        let cnf = two_vars();
+        assert_fails(&cnf, b"5 0 1 0\n", "hint clause 1 is not unit");
+        assert_fails(&cnf, b"5 2 0 3 0\n", "hint clause 3 is satisfied");
+        assert_fails(&cnf, b"5 2 0 7 0\n", "unknown clause 7 in hints");
+        assert_fails(&cnf, b"5 0 0\n", "hints do not lead to a conflict");
+    }
+
+    #[test]
+    fn missing_rat_hints() {
+        let cnf = cnf_of(&[&[-1, 2], &[2, 3], &[2, -3], &[-2, 4], &[-2, -4]]);
+        assert_fails(
+            &cnf,
+            b"6 1 0 0\n7 0 6 1 4 5 0\n",
+            "missing RAT hints for clause 1",
+        );
+    }
+
+    #[test]
+    fn non_increasing_ids() {
+        let cnf = two_vars();
+        assert_fails(&cnf, b"4 2 0 1 2 0\n", "identifiers must increase");
+        assert_fails(
+            &cnf,
+            b"5 2 0 1 2 0\n5 0 5 3 4 0\n",
+            "identifiers must increase",
+        );
+    }
+
+    #[test]
+    fn deleted_clause() {
+        assert_fails(
+            &two_vars(),
+            b"5 2 0 1 2 0\n5 d 5 0\n6 0 5 3 4 0\n",
+            "unknown clause 5 in hints",
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Branching heuristics, *i.e.* how the *unsat* rule of the [recursive solvers][crate::recursive]
+//! picks the literal it branches on.
+//!
+//! The paper branches on the first literal of the first clause, which is [`Branching::First`].
+//! Heuristics only see the clauses that are not satisfied yet, simplified by the environment: all
+//! their literals are unassigned. Ties are broken deterministically so that runs are reproducible.
+
+use crate::{prelude::*, rng::Rng};
+
+/// A branching heuristic.
+pub trait Branch<Lit: Literal> {
+    /// Picks the literal to branch on, `δ` is not empty and none of its clauses is empty.
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit;
+    /// Notifies the heuristic of a conflict, `clause` is implied by the formula and falsified by the
+    /// decisions that led to the conflict.
+    fn conflict(&mut self, _clause: &[Lit]) {}
+}
+
+/// Enumerates the branching heuristics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Branching {
+    /// First literal of the first clause, as in the paper.
+    #[default]
+    First,
+    /// *Maximum Occurrences in clauses of Minimum Size*.
+    Moms,
+    /// (One-sided) Jeroslow-Wang, favors literals appearing in many short clauses.
+    JeroslowWang,
+    /// *Dynamic Largest Individual Sum*, the literal appearing in the most clauses.
+    Dlis,
+    /// Uniformly random literal occurrence, with a seed.
+    Random(u64),
+    /// VSIDS-like activity, bumped by conflicts and decaying over time.
+    Activity,
+}
+implem! {
+    for Branching {
+        Display {
+            |&self, fmt| match self {
+                Self::First => "first".fmt(fmt),
+                Self::Moms => "moms".fmt(fmt),
+                Self::JeroslowWang => "jw".fmt(fmt),
+                Self::Dlis => "dlis".fmt(fmt),
+                Self::Random(seed) => write!(fmt, "random:{}", seed),
+                Self::Activity => "activity".fmt(fmt),
+            }
+        }
+    }
+}
+impl Branching {
+    pub const NAMES: &'static [(&'static str, &'static str)] = &[
+        (
+            "first",
+            "First literal of the first clause, as in the paper",
+        ),
+        ("moms", "Maximum Occurrences in clauses of Minimum Size"),
+        (
+            "jw",
+            "Jeroslow-Wang, favors literals appearing in many short clauses",
+        ),
+        (
+            "dlis",
+            "Dynamic Largest Individual Sum, literal appearing in the most clauses",
+        ),
+        (
+            "random[:<seed>]",
+            "Random literal, the seed defaults to `0`",
+        ),
+        ("activity", "VSIDS-like activity bumped by conflicts"),
+    ];
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, seed) = match name.split_once(':') {
+            Some((name, seed)) => (name, Some(seed.parse().ok()?)),
+            None => (name, None),
+        };
+        match (name, seed) {
+            ("first", None) => Some(Self::First),
+            ("moms", None) => Some(Self::Moms),
+            ("jw", None) => Some(Self::JeroslowWang),
+            ("dlis", None) => Some(Self::Dlis),
+            ("random", seed) => Some(Self::Random(seed.unwrap_or(0))),
+            ("activity", None) => Some(Self::Activity),
+            _ => None,
+        }
+    }
+
+    /// Creates the heuristic.
+    pub fn build<'a, Lit: Literal + 'a>(self) -> Box<dyn Branch<Lit> + 'a> {
+        match self {
+            Self::First => Box::new(First),
+            Self::Moms => Box::new(Moms),
+            Self::JeroslowWang => Box::new(JeroslowWang),
+            Self::Dlis => Box::new(Dlis),
+            Self::Random(seed) => Box::new(Random::new(seed)),
+            Self::Activity => Box::new(Activity::new()),
+        }
+    }
+}
+
+/// Literal with the best score, ties are broken in favor of the smallest literal since the order of
+/// the scores is not deterministic.
+fn best<Lit: Literal, S: PartialOrd>(scores: impl Iterator<Item = (Lit, S)>) -> Option<Lit> {
+    let mut best: Option<(Lit, S)> = None;
+    for (lit, score) in scores {
+        let better = match &best {
+            None => true,
+            Some((best_lit, best_score)) => {
+                score > *best_score || (score == *best_score && lit < *best_lit)
+            }
+        };
+        if better {
+            best = Some((lit, score))
+        }
+    }
+    best.map(|(lit, _)| lit)
+}
+
+/// Branches on the first literal of the first clause.
+pub struct First;
+impl<Lit: Literal> Branch<Lit> for First {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        δ.next()
+            .and_then(|clause| clause.iter().next())
+            .expect("illegal empty CNF or disjunct in branching")
+            .clone()
+    }
+}
+
+/// Branches on the variable occurring the most in the shortest clauses.
+///
+/// The score of a variable `x` is `(f(x) + f(¬x)) · 2^k + f(x) · f(¬x)`, with `f(l)` the number of
+/// occurrences of `l` in the shortest clauses. The polarity is the one occurring the most.
+pub struct Moms;
+impl Moms {
+    const K: u32 = 10;
+}
+impl<Lit: Literal> Branch<Lit> for Moms {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        let mut min_len = usize::MAX;
+        let mut counts: Map<Lit, u64> = Map::new();
+        for clause in δ {
+            if clause.len() < min_len {
+                min_len = clause.len();
+                counts.clear()
+            }
+            if clause.len() == min_len {
+                for lit in clause.iter() {
+                    *counts.entry(lit.clone()).or_insert(0) += 1
+                }
+            }
+        }
+        let scores = counts.iter().map(|(lit, count)| {
+            let neg_count = counts.get(&lit.ref_negate()).cloned().unwrap_or(0);
+            let score = ((count + neg_count) << Self::K) + count * neg_count;
+            // Literals of the same variable have the same score, favor the most frequent one.
+            (lit.clone(), (score, *count))
+        });
+        best(scores).expect("illegal empty CNF in branching")
+    }
+}
+
+/// Branches on the literal `l` maximizing `Σ 2^-|c|` over the clauses `c` it appears in.
+pub struct JeroslowWang;
+impl<Lit: Literal> Branch<Lit> for JeroslowWang {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        let mut scores: Map<Lit, f64> = Map::new();
+        for clause in δ {
+            let weight = 2f64.powi(-(clause.len() as i32));
+            for lit in clause.iter() {
+                *scores.entry(lit.clone()).or_insert(0.) += weight
+            }
+        }
+        best(scores.into_iter()).expect("illegal empty CNF in branching")
+    }
+}
+
+/// Branches on the literal appearing in the most clauses.
+pub struct Dlis;
+impl<Lit: Literal> Branch<Lit> for Dlis {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        let mut counts: Map<Lit, usize> = Map::new();
+        for clause in δ {
+            for lit in clause.iter() {
+                *counts.entry(lit.clone()).or_insert(0) += 1
+            }
+        }
+        best(counts.into_iter()).expect("illegal empty CNF in branching")
+    }
+}
+
+/// Branches on a literal occurrence picked uniformly at random.
+pub struct Random {
+    rng: Rng,
+}
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+impl<Lit: Literal> Branch<Lit> for Random {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        // Reservoir sampling over all literal occurrences.
+        let mut picked = None;
+        let mut seen = 0u64;
+        for clause in δ {
+            for lit in clause.iter() {
+                seen += 1;
+                if self.rng.next_u64().is_multiple_of(seen) {
+                    picked = Some(lit)
+                }
+            }
+        }
+        picked.expect("illegal empty CNF in branching").clone()
+    }
+}
+
+/// Branches on the most active literal.
+///
+/// Conflicts bump the activity of the literals of the conflict clause. Bumps increase
+/// geometrically, so that older bumps decay relative to recent ones.
+pub struct Activity<Lit: Literal> {
+    activity: Map<Lit, f64>,
+    /// Current bump.
+    inc: f64,
+}
+impl<Lit: Literal> Activity<Lit> {
+    /// Activity decay factor.
+    const DECAY: f64 = 0.95;
+    /// Activities are rescaled when one of them exceeds this value.
+    const RESCALE: f64 = 1e100;
+
+    pub fn new() -> Self {
+        Self {
+            activity: Map::new(),
+            inc: 1.,
+        }
+    }
+}
+impl<Lit: Literal> Default for Activity<Lit> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Lit: Literal> Branch<Lit> for Activity<Lit> {
+    fn pick(&mut self, δ: &mut dyn Iterator<Item = &Clause<Lit>>) -> Lit {
+        // Ties are broken in favor of the first occurrence, so that without conflicts this is the
+        // order of the paper.
+        let mut best: Option<(&Lit, f64)> = None;
+        for clause in δ {
+            for lit in clause.iter() {
+                let score = self.activity.get(lit).cloned().unwrap_or(0.);
+                if best.is_none_or(|(_, best_score)| score > best_score) {
+                    best = Some((lit, score))
+                }
+            }
+        }
+        best.expect("illegal empty CNF in branching").0.clone()
+    }
+
+    fn conflict(&mut self, clause: &[Lit]) {
+        let mut rescale = false;
+        for lit in clause {
+            let activity = self.activity.entry(lit.clone()).or_insert(0.);
+            *activity += self.inc;
+            rescale = rescale || *activity > Self::RESCALE
+        }
+        if rescale {
+            for activity in self.activity.values_mut() {
+                *activity /= Self::RESCALE
+            }
+            self.inc /= Self::RESCALE
+        }
+        self.inc /= Self::DECAY
+    }
+}
@@ -0,0 +1,57 @@
+//! Cooperative cancellation of a search.
+//!
+//! Solvers poll a [`Cancel`] token before each decision (and each conflict for the
+//! [imperative][crate::imperative] solver), and stop with [`Outcome::Unknown`] as soon as it is
+//! cancelled.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Cancellation token, with an optional deadline.
+///
+/// Clones share the same flag: cancelling a clone cancels all of them, which lets some thread
+/// interrupt a solver running in another thread.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel {
+    /// Set by [`Self::cancel`].
+    flag: Arc<AtomicBool>,
+    /// Instant after which the token is cancelled, if any.
+    deadline: Option<Instant>,
+}
+impl Cancel {
+    /// Token that is only cancelled by [`Self::cancel`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Token that is cancelled at `deadline`, or by [`Self::cancel`].
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            flag: Arc::new(AtomicBool::new(false)),
+            deadline: Some(deadline),
+        }
+    }
+    /// Token that is cancelled after `timeout`, or by [`Self::cancel`].
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Deadline of the token, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Cancels the token and all its clones.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed)
+    }
+
+    /// True if the token was cancelled or its deadline is reached.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
@@ -0,0 +1,483 @@
+//! Boolean expressions over named variables, and their conversion to CNF.
+//!
+//! An [`Expr`] is a [`Formula`]: turning it into a CNF introduces *auxiliary* variables standing
+//! for its subexpressions, using either the [Tseitin] or the [Plaisted-Greenbaum] encoding (see
+//! [`Encoding`]). Both are equisatisfiable with the original expression, and models of the CNF
+//! restricted to the named variables are models of the expression.
+//!
+//! Each encoding numbers its auxiliary variables from `0`, so that encoding the same expression
+//! always yields the same CNF. To conjoin the CNFs of different expressions, assert all of them
+//! in the same [`Encoder`], or start an encoder where a previous one stopped with
+//! [`Encoder::with_next_aux`].
+//!
+//! [Tseitin]: https://en.wikipedia.org/wiki/Tseytin_transformation
+//! [Plaisted-Greenbaum]: https://doi.org/10.1016/S0747-7171(86)80028-1
+
+use crate::prelude::*;
+
+/// Types that can be used as variable names.
+pub trait Name: Clone + PartialEq + Eq + PartialOrd + Ord + Hash + Display {}
+impl<T: Clone + PartialEq + Eq + PartialOrd + Ord + Hash + Display> Name for T {}
+
+/// A variable, either named or auxiliary.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Var<V> {
+    /// Variable from an expression.
+    Named(V),
+    /// Variable introduced by CNF conversion.
+    Aux(usize),
+}
+implem! {
+    impl(V: Display) for Var<V> {
+        Display {
+            |&self, fmt| match self {
+                Self::Named(name) => name.fmt(fmt),
+                Self::Aux(idx) => write!(fmt, "#{}", idx),
+            }
+        }
+    }
+}
+
+/// A literal, *i.e.* a possibly negated variable.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lit<V> {
+    var: Var<V>,
+    neg: bool,
+}
+impl<V> Lit<V> {
+    /// Positive literal for a named variable.
+    pub fn named(name: V) -> Self {
+        Self {
+            var: Var::Named(name),
+            neg: false,
+        }
+    }
+    /// Positive literal for an auxiliary variable.
+    fn aux(idx: usize) -> Self {
+        Self {
+            var: Var::Aux(idx),
+            neg: false,
+        }
+    }
+
+    /// Variable of this literal.
+    pub fn var(&self) -> &Var<V> {
+        &self.var
+    }
+    /// Name of the variable of this literal, `None` if it is auxiliary.
+    pub fn name(&self) -> Option<&V> {
+        match &self.var {
+            Var::Named(name) => Some(name),
+            Var::Aux(_) => None,
+        }
+    }
+    /// True if the literal is negative.
+    pub fn is_neg(&self) -> bool {
+        self.neg
+    }
+}
+implem! {
+    impl(V: Display) for Lit<V> {
+        Display {
+            |&self, fmt| {
+                if self.neg {
+                    write!(fmt, "-")?
+                }
+                self.var.fmt(fmt)
+            }
+        }
+    }
+}
+impl<V: Name> Literal for Lit<V> {
+    fn negate(self) -> Self {
+        Self {
+            var: self.var,
+            neg: !self.neg,
+        }
+    }
+    fn ref_negate(&self) -> Self {
+        self.clone().negate()
+    }
+}
+
+/// A Boolean expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr<V> {
+    /// Constant.
+    Const(bool),
+    /// Named variable.
+    Var(V),
+    /// Negation.
+    Not(Box<Expr<V>>),
+    /// Conjunction, true if empty.
+    And(Vec<Expr<V>>),
+    /// Disjunction, false if empty.
+    Or(Vec<Expr<V>>),
+    /// Implication.
+    Implies(Box<Expr<V>>, Box<Expr<V>>),
+    /// Equivalence.
+    Iff(Box<Expr<V>>, Box<Expr<V>>),
+    /// Exclusive disjunction.
+    Xor(Box<Expr<V>>, Box<Expr<V>>),
+    /// If-then-else: condition, then branch, else branch.
+    Ite(Box<Expr<V>>, Box<Expr<V>>, Box<Expr<V>>),
+}
+impl<V> Expr<V> {
+    /// Variable constructor.
+    pub fn var(name: V) -> Self {
+        Self::Var(name)
+    }
+    /// Conjunction constructor.
+    pub fn and(es: impl IntoIterator<Item = Self>) -> Self {
+        Self::And(es.into_iter().collect())
+    }
+    /// Disjunction constructor.
+    pub fn or(es: impl IntoIterator<Item = Self>) -> Self {
+        Self::Or(es.into_iter().collect())
+    }
+    /// Implication constructor.
+    pub fn implies(lhs: Self, rhs: Self) -> Self {
+        Self::Implies(Box::new(lhs), Box::new(rhs))
+    }
+    /// Equivalence constructor.
+    pub fn iff(lhs: Self, rhs: Self) -> Self {
+        Self::Iff(Box::new(lhs), Box::new(rhs))
+    }
+    /// Exclusive disjunction constructor.
+    pub fn xor(lhs: Self, rhs: Self) -> Self {
+        Self::Xor(Box::new(lhs), Box::new(rhs))
+    }
+    /// If-then-else constructor.
+    pub fn ite(cnd: Self, thn: Self, els: Self) -> Self {
+        Self::Ite(Box::new(cnd), Box::new(thn), Box::new(els))
+    }
+
+    /// Evaluates an expression given the value of its variables.
+    pub fn eval(&self, value: &impl Fn(&V) -> bool) -> bool {
+        match self {
+            Self::Const(b) => *b,
+            Self::Var(name) => value(name),
+            Self::Not(e) => !e.eval(value),
+            Self::And(es) => es.iter().all(|e| e.eval(value)),
+            Self::Or(es) => es.iter().any(|e| e.eval(value)),
+            Self::Implies(lhs, rhs) => !lhs.eval(value) || rhs.eval(value),
+            Self::Iff(lhs, rhs) => lhs.eval(value) == rhs.eval(value),
+            Self::Xor(lhs, rhs) => lhs.eval(value) != rhs.eval(value),
+            Self::Ite(cnd, thn, els) => {
+                if cnd.eval(value) {
+                    thn.eval(value)
+                } else {
+                    els.eval(value)
+                }
+            }
+        }
+    }
+
+    /// Attaches an encoding to an expression, to use it as a [`Formula`].
+    pub fn encoded(self, encoding: Encoding) -> Encoded<V> {
+        Encoded {
+            expr: self,
+            encoding,
+        }
+    }
+}
+impl<V: Name> Expr<V> {
+    /// CNF conversion.
+    pub fn to_cnf(&self, encoding: Encoding) -> Cnf<Lit<V>> {
+        let mut encoder = Encoder::new(encoding);
+        encoder.assert(self);
+        encoder.cnf
+    }
+}
+/// Negation constructor.
+impl<V> std::ops::Not for Expr<V> {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
+/// Uses the [Tseitin](Encoding::Tseitin) encoding.
+impl<V: Name> Formula for Expr<V> {
+    type Lit = Lit<V>;
+    fn into_cnf(self) -> Cnf<Lit<V>> {
+        self.to_cnf(Encoding::Tseitin)
+    }
+}
+
+/// CNF encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Tseitin encoding, auxiliary variables are equivalent to the subexpression they stand for.
+    #[default]
+    Tseitin,
+    /// Plaisted-Greenbaum encoding, auxiliary variables only imply (or are implied by) the
+    /// subexpression they stand for, depending on the polarity of the subexpression.
+    ///
+    /// Produces fewer clauses than [`Self::Tseitin`], but models of the CNF do not necessarily
+    /// give auxiliary variables the value of their subexpression.
+    PlaistedGreenbaum,
+}
+
+/// An expression with an encoding.
+#[derive(Debug, Clone)]
+pub struct Encoded<V> {
+    pub expr: Expr<V>,
+    pub encoding: Encoding,
+}
+impl<V: Name> Formula for Encoded<V> {
+    type Lit = Lit<V>;
+    fn into_cnf(self) -> Cnf<Lit<V>> {
+        self.expr.to_cnf(self.encoding)
+    }
+}
+
+/// Polarity of a subexpression: whether it appears under an even number of negations, an odd
+/// one, or both (*e.g.* under an equivalence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Polarity {
+    Pos,
+    Neg,
+    Both,
+}
+impl Polarity {
+    fn flip(self) -> Self {
+        match self {
+            Self::Pos => Self::Neg,
+            Self::Neg => Self::Pos,
+            Self::Both => Self::Both,
+        }
+    }
+    fn has_pos(self) -> bool {
+        self != Self::Neg
+    }
+    fn has_neg(self) -> bool {
+        self != Self::Pos
+    }
+}
+
+/// CNF encoder, the CNF is the conjunction of the expressions [asserted][Self::assert] so far.
+pub struct Encoder<V: Name> {
+    encoding: Encoding,
+    cnf: Cnf<Lit<V>>,
+    /// Index of the next auxiliary variable.
+    next_aux: usize,
+    /// Literal that is always true, if one was needed.
+    tru: Option<Lit<V>>,
+}
+impl<V: Name> Formula for Encoder<V> {
+    type Lit = Lit<V>;
+    fn into_cnf(self) -> Cnf<Lit<V>> {
+        self.cnf
+    }
+}
+impl<V: Name> Encoder<V> {
+    /// Constructor, auxiliary variables start at `0`.
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            cnf: Cnf::empty(),
+            next_aux: 0,
+            tru: None,
+        }
+    }
+
+    /// Sets the index of the first auxiliary variable, typically the [`Self::next_aux`] of a
+    /// previous encoder so that the CNFs of both can be conjoined.
+    pub fn with_next_aux(mut self, next_aux: usize) -> Self {
+        self.next_aux = next_aux;
+        self
+    }
+
+    /// Index of the next auxiliary variable, all the ones in the CNF are lower.
+    pub fn next_aux(&self) -> usize {
+        self.next_aux
+    }
+
+    /// CNF accessor, see also [`Formula::into_cnf`].
+    pub fn cnf(&self) -> &Cnf<Lit<V>> {
+        &self.cnf
+    }
+
+    /// Positive literal for a fresh auxiliary variable.
+    fn fresh(&mut self) -> Lit<V> {
+        let lit = Lit::aux(self.next_aux);
+        self.next_aux += 1;
+        lit
+    }
+
+    fn clause(&mut self, lits: impl IntoIterator<Item = Lit<V>>) {
+        let mut clause = Clause::with_capacity(3);
+        for lit in lits {
+            clause.push(lit)
+        }
+        self.cnf.push(clause)
+    }
+
+    /// A literal that's always true.
+    fn tru(&mut self) -> Lit<V> {
+        if let Some(tru) = &self.tru {
+            return tru.clone();
+        }
+        let tru = self.fresh();
+        self.clause(Some(tru.clone()));
+        self.tru = Some(tru.clone());
+        tru
+    }
+
+    /// Adds the clauses stating that an expression is true.
+    ///
+    /// Top-level conjunctions and disjunctions do not need auxiliary variables.
+    pub fn assert(&mut self, e: &Expr<V>) {
+        match e {
+            Expr::Const(true) => (),
+            Expr::And(es) => {
+                for e in es {
+                    self.assert(e)
+                }
+            }
+            Expr::Or(es) => {
+                let lits: Vec<_> = es.iter().map(|e| self.encode(e, Polarity::Pos)).collect();
+                self.clause(lits)
+            }
+            _ => {
+                let lit = self.encode(e, Polarity::Pos);
+                self.clause(Some(lit))
+            }
+        }
+    }
+
+    /// Adds the clauses defining a fresh auxiliary literal.
+    ///
+    /// `pos` are the clauses for "literal implies definition", and `neg` the ones for
+    /// "definition implies literal". The literal is the first element of each clause.
+    fn define(&mut self, pol: Polarity, pos: Vec<Vec<Lit<V>>>, neg: Vec<Vec<Lit<V>>>) -> Lit<V> {
+        let lit = self.fresh();
+        let tseitin = self.encoding == Encoding::Tseitin;
+        if tseitin || pol.has_pos() {
+            for lits in pos {
+                self.clause(Some(lit.ref_negate()).into_iter().chain(lits))
+            }
+        }
+        if tseitin || pol.has_neg() {
+            for lits in neg {
+                self.clause(Some(lit.clone()).into_iter().chain(lits))
+            }
+        }
+        lit
+    }
+
+    /// Encodes an expression appearing with some polarity, yields the literal standing for it.
+    fn encode(&mut self, e: &Expr<V>, pol: Polarity) -> Lit<V> {
+        match e {
+            Expr::Const(b) => {
+                let tru = self.tru();
+                if *b {
+                    tru
+                } else {
+                    tru.negate()
+                }
+            }
+            Expr::Var(name) => Lit::named(name.clone()),
+            Expr::Not(e) => self.encode(e, pol.flip()).negate(),
+            Expr::And(es) => {
+                let lits: Vec<_> = es.iter().map(|e| self.encode(e, pol)).collect();
+                self.and(pol, lits)
+            }
+            Expr::Or(es) => {
+                let lits: Vec<_> = es.iter().map(|e| self.encode(e, pol)).collect();
+                self.or(pol, lits)
+            }
+            Expr::Implies(lhs, rhs) => {
+                let lhs = self.encode(lhs, pol.flip());
+                let rhs = self.encode(rhs, pol);
+                self.or(pol, vec![lhs.negate(), rhs])
+            }
+            Expr::Iff(lhs, rhs) => {
+                let a = self.encode(lhs, Polarity::Both);
+                let b = self.encode(rhs, Polarity::Both);
+                let (na, nb) = (a.ref_negate(), b.ref_negate());
+                self.define(
+                    pol,
+                    vec![vec![na.clone(), b.clone()], vec![a.clone(), nb.clone()]],
+                    vec![vec![a, b], vec![na, nb]],
+                )
+            }
+            Expr::Xor(lhs, rhs) => {
+                let a = self.encode(lhs, Polarity::Both);
+                let b = self.encode(rhs, Polarity::Both);
+                let (na, nb) = (a.ref_negate(), b.ref_negate());
+                self.define(
+                    pol,
+                    vec![vec![a.clone(), b.clone()], vec![na.clone(), nb.clone()]],
+                    vec![vec![na, b], vec![a, nb]],
+                )
+            }
+            Expr::Ite(cnd, thn, els) => {
+                let c = self.encode(cnd, Polarity::Both);
+                let t = self.encode(thn, pol);
+                let e = self.encode(els, pol);
+                let nc = c.ref_negate();
+                self.define(
+                    pol,
+                    vec![vec![nc.clone(), t.clone()], vec![c.clone(), e.clone()]],
+                    vec![vec![nc, t.negate()], vec![c, e.negate()]],
+                )
+            }
+        }
+    }
+
+    /// Defines a literal equivalent to the conjunction of some literals.
+    fn and(&mut self, pol: Polarity, lits: Vec<Lit<V>>) -> Lit<V> {
+        let neg = vec![lits.iter().map(Literal::ref_negate).collect()];
+        let pos = lits.into_iter().map(|lit| vec![lit]).collect();
+        self.define(pol, pos, neg)
+    }
+
+    /// Defines a literal equivalent to the disjunction of some literals.
+    fn or(&mut self, pol: Polarity, lits: Vec<Lit<V>>) -> Lit<V> {
+        let neg = lits.iter().map(|lit| vec![lit.ref_negate()]).collect();
+        let pos = vec![lits];
+        self.define(pol, pos, neg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr() -> Expr<&'static str> {
+        Expr::iff(Expr::var("a"), Expr::or([Expr::var("b"), !Expr::var("c")]))
+    }
+
+    #[test]
+    fn reproducible() {
+        for encoding in [Encoding::Tseitin, Encoding::PlaistedGreenbaum] {
+            assert_eq!(*expr().to_cnf(encoding), *expr().to_cnf(encoding));
+        }
+    }
+
+    /// Auxiliary variables of a CNF.
+    fn aux(cnf: &Cnf<Lit<&'static str>>) -> Set<usize> {
+        cnf.iter()
+            .flat_map(|clause| clause.iter())
+            .filter_map(|lit| match lit.var() {
+                Var::Aux(idx) => Some(*idx),
+                Var::Named(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn continued_numbering() {
+        let mut encoder = Encoder::new(Encoding::Tseitin);
+        encoder.assert(&expr());
+        let first = aux(encoder.cnf());
+        assert!(first.iter().all(|idx| *idx < encoder.next_aux()));
+
+        let mut next = Encoder::new(Encoding::Tseitin).with_next_aux(encoder.next_aux());
+        next.assert(&expr());
+        let second = aux(next.cnf());
+        assert!(!second.is_empty());
+        assert!(first.is_disjoint(&second));
+    }
+}
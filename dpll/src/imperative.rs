@@ -0,0 +1,876 @@
+//! Imperative CDCL, with an assignment trail, decision levels and two watched literals.
+//!
+//! Unlike the [recursive][crate::recursive] and [iterative][crate::iterative] versions, this solver
+//! is not derived from the rules of the paper. It is a classic MiniSat-like CDCL: propagation uses
+//! two watched literals, conflicts are analyzed up to the first UIP (*Unique Implication Point*),
+//! and decisions are driven by VSIDS activity with phase saving. Restarts follow a [restart
+//! policy][crate::restart], learned clauses survive them but can be deleted by reductions of the
+//! [learned clause database][crate::learned].
+
+use crate::{learned::Reducer, prelude::*, proof::Proof, restart::Policy, stats::Stats};
+
+/// Solves a formula, logging a proof of unsatisfiability to `proof` (if any).
+///
+/// Only the restart policy and learned clause database settings of `options` are used. Yields
+/// [`Outcome::Unknown`] if `cancel` is cancelled before the search ends.
+pub fn solve<F>(
+    f: F,
+    options: &Options,
+    proof: Option<&mut (dyn Proof<F::Lit> + '_)>,
+    cancel: &Cancel,
+) -> Result<(Outcome<F::Lit, ()>, Stats), String>
+where
+    F: Formula,
+{
+    let mut solver = Cdcl::new(f);
+    solver.set_restarts(options.restarts);
+    solver.set_reduction(options.reduction);
+    solver.set_cancel(cancel.clone());
+    let outcome = solver.solve_with_proof(proof);
+    Ok((outcome, solver.stats))
+}
+
+/// Internal literal, the index of its variable times two, plus one if the literal is negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ILit(u32);
+impl ILit {
+    fn new(var: usize, neg: bool) -> Self {
+        Self(((var as u32) << 1) | neg as u32)
+    }
+    fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+    fn is_neg(self) -> bool {
+        self.0 & 1 == 1
+    }
+    /// Index of this literal in literal-indexed vectors.
+    fn idx(self) -> usize {
+        self.0 as usize
+    }
+    fn negate(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// A clause in the clause arena.
+#[derive(Debug, Clone)]
+struct IClause {
+    /// Literals, the first two are the ones being watched. Empty for deleted clauses.
+    lits: Vec<ILit>,
+    /// LBD of learned clauses when they were learned, `None` for original clauses.
+    lbd: Option<usize>,
+    /// Activity of learned clauses.
+    activity: f64,
+}
+
+/// An entry in a watch list.
+#[derive(Debug, Clone, Copy)]
+struct Watcher {
+    /// Index of the clause in the arena.
+    cref: usize,
+    /// Some literal of the clause, if it is true the clause does not need to be visited.
+    blocker: ILit,
+}
+
+/// Max-heap of variables ordered by activity.
+#[derive(Debug, Clone, Default)]
+struct VarOrder {
+    heap: Vec<usize>,
+    /// Position of each variable in `heap`, if any.
+    indices: Vec<Option<usize>>,
+}
+impl VarOrder {
+    fn contains(&self, var: usize) -> bool {
+        self.indices[var].is_some()
+    }
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if var >= self.indices.len() {
+            self.indices.resize(var + 1, None)
+        }
+        if self.contains(var) {
+            return;
+        }
+        self.indices[var] = Some(self.heap.len());
+        self.heap.push(var);
+        self.sift_up(self.heap.len() - 1, activity)
+    }
+    /// Restores the heap invariant after the activity of `var` increased.
+    fn increased(&mut self, var: usize, activity: &[f64]) {
+        if let Some(pos) = self.indices[var] {
+            self.sift_up(pos, activity)
+        }
+    }
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.indices[top] = None;
+        if let Some(&var) = self.heap.first() {
+            self.indices[var] = Some(0);
+            self.sift_down(0, activity)
+        }
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut pos: usize, activity: &[f64]) {
+        let var = self.heap[pos];
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if activity[self.heap[parent]] >= activity[var] {
+                break;
+            }
+            self.heap[pos] = self.heap[parent];
+            self.indices[self.heap[pos]] = Some(pos);
+            pos = parent;
+        }
+        self.heap[pos] = var;
+        self.indices[var] = Some(pos);
+    }
+    fn sift_down(&mut self, mut pos: usize, activity: &[f64]) {
+        let var = self.heap[pos];
+        loop {
+            let left = 2 * pos + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len()
+                && activity[self.heap[right]] > activity[self.heap[left]]
+            {
+                right
+            } else {
+                left
+            };
+            if activity[self.heap[child]] <= activity[var] {
+                break;
+            }
+            self.heap[pos] = self.heap[child];
+            self.indices[self.heap[pos]] = Some(pos);
+            pos = child;
+        }
+        self.heap[pos] = var;
+        self.indices[var] = Some(pos);
+    }
+}
+
+/// Imperative CDCL solver.
+#[derive(Clone)]
+pub struct Cdcl<Lit: Literal> {
+    /// Maps the representative of a variable to its index, see [`Self::representative`].
+    vars: Map<Lit, usize>,
+    /// Representative of each variable.
+    reps: Vec<Lit>,
+
+    /// Clause arena.
+    clauses: Vec<IClause>,
+    /// Learned clauses in the arena.
+    learnts: Vec<usize>,
+    /// Slots of deleted clauses in the arena, reused by new clauses.
+    free: Vec<usize>,
+    /// Watch lists, indexed by literals: clauses watching a literal are visited when it is
+    /// falsified.
+    watches: Vec<Vec<Watcher>>,
+    /// Unit clauses from the original formula.
+    units: Vec<ILit>,
+    /// False if the original formula contains the empty clause.
+    ok: bool,
+
+    /// Value of each variable, if any.
+    assigns: Vec<Option<bool>>,
+    /// Decision level of each assigned variable.
+    level: Vec<usize>,
+    /// Clause that propagated each assigned variable, `None` for decisions.
+    reason: Vec<Option<usize>>,
+    /// Assigned literals, in chronological order.
+    trail: Vec<ILit>,
+    /// Position in `trail` of the first literal of each decision level.
+    trail_lim: Vec<usize>,
+    /// Position in `trail` of the next literal to propagate.
+    qhead: usize,
+
+    /// VSIDS activity of each variable.
+    activity: Vec<f64>,
+    /// Current activity bump.
+    var_inc: f64,
+    /// Current learned clause activity bump.
+    cla_inc: f64,
+    /// Unassigned variables (and maybe some assigned ones) by activity.
+    order: VarOrder,
+    /// Last value of each variable, used to pick the polarity of decisions.
+    polarity: Vec<bool>,
+
+    /// Scratch marks used during conflict analysis.
+    seen: Vec<bool>,
+    /// Restart policy, see [`Self::set_restarts`].
+    restart: Policy,
+    /// Learned clause database reductions, see [`Self::set_reduction`].
+    reducer: Reducer,
+
+    /// Assumptions of the current search, decided (in order) before any other literal.
+    assumptions: Vec<ILit>,
+    /// Assumptions responsible for the last unsat result, see [`Self::failed_assumptions`].
+    conflict: Vec<ILit>,
+
+    /// Statistics accumulated over all searches.
+    stats: Stats,
+    /// Polled before each decision and after each conflict, see [`Self::set_cancel`].
+    cancel: Cancel,
+}
+
+implem! {
+    impl(Lit: Literal, F: Formula<Lit = Lit>) for Cdcl<Lit> {
+        From<F> {
+            |f| Self::new(f),
+        }
+    }
+}
+
+impl<Lit: Literal> Cdcl<Lit> {
+    /// Activity decay factor.
+    const VAR_DECAY: f64 = 0.95;
+    /// Learned clause activity decay factor.
+    const CLAUSE_DECAY: f64 = 0.999;
+
+    /// Construct a solver from a formula.
+    pub fn new<F: Formula<Lit = Lit>>(f: F) -> Self {
+        let mut slf = Self {
+            vars: Map::new(),
+            reps: vec![],
+
+            clauses: vec![],
+            learnts: vec![],
+            free: vec![],
+            watches: vec![],
+            units: vec![],
+            ok: true,
+
+            assigns: vec![],
+            level: vec![],
+            reason: vec![],
+            trail: vec![],
+            trail_lim: vec![],
+            qhead: 0,
+
+            activity: vec![],
+            var_inc: 1.0,
+            cla_inc: 1.0,
+            order: VarOrder::default(),
+            polarity: vec![],
+
+            seen: vec![],
+            restart: Restarts::Never.build(),
+            reducer: Reduction::new().build(),
+
+            assumptions: vec![],
+            conflict: vec![],
+
+            stats: Stats::new(),
+            cancel: Cancel::new(),
+        };
+        for clause in f.into_cnf().into_iter() {
+            slf.add_clause(clause)
+        }
+        slf
+    }
+
+    /// Representative of the variable of a literal, and whether the literal is its negation.
+    fn representative(lit: &Lit) -> (Lit, bool) {
+        let nlit = lit.ref_negate();
+        if *lit <= nlit {
+            (lit.clone(), false)
+        } else {
+            (nlit, true)
+        }
+    }
+
+    /// Internal version of a literal, creates its variable if needed.
+    fn intern(&mut self, lit: &Lit) -> ILit {
+        let (rep, neg) = Self::representative(lit);
+        let var = match self.vars.get(&rep) {
+            Some(var) => *var,
+            None => {
+                let var = self.reps.len();
+                self.vars.insert(rep.clone(), var);
+                self.reps.push(rep);
+                self.watches.push(vec![]);
+                self.watches.push(vec![]);
+                self.assigns.push(None);
+                self.level.push(0);
+                self.reason.push(None);
+                self.activity.push(0.0);
+                self.polarity.push(false);
+                self.seen.push(false);
+                self.order.insert(var, &self.activity);
+                var
+            }
+        };
+        ILit::new(var, neg)
+    }
+
+    /// External version of an internal literal.
+    fn external(&self, lit: ILit) -> Lit {
+        let rep = &self.reps[lit.var()];
+        if lit.is_neg() {
+            rep.ref_negate()
+        } else {
+            rep.clone()
+        }
+    }
+
+    /// Adds a clause to the formula, undoes all decisions if needed.
+    pub fn add_clause(&mut self, clause: Clause<Lit>) {
+        self.cancel_until(0);
+        let mut lits: Vec<ILit> = Vec::with_capacity(clause.len());
+        for lit in clause.iter() {
+            let lit = self.intern(lit);
+            if lits.contains(&lit.negate()) || self.value(lit) == Some(true) {
+                // Tautology or satisfied at level 0, ignore clause.
+                return;
+            } else if !lits.contains(&lit) {
+                lits.push(lit)
+            }
+        }
+        // Literals falsified at level 0 cannot be watched, move them last.
+        lits.sort_by_key(|lit| self.value(*lit).is_some());
+        match lits
+            .iter()
+            .filter(|lit| self.value(**lit).is_none())
+            .count()
+        {
+            0 => self.ok = false,
+            1 => self.units.push(lits[0]),
+            _ => {
+                self.attach(lits, None);
+            }
+        }
+    }
+
+    /// Adds a clause to the arena and watches its first two literals.
+    ///
+    /// `lbd` is the LBD of learned clauses, `None` for original clauses.
+    fn attach(&mut self, lits: Vec<ILit>, lbd: Option<usize>) -> usize {
+        debug_assert!(lits.len() >= 2);
+        let clause = IClause {
+            lits: vec![],
+            lbd,
+            activity: 0.0,
+        };
+        let cref = match self.free.pop() {
+            Some(cref) => {
+                self.clauses[cref] = clause;
+                cref
+            }
+            None => {
+                self.clauses.push(clause);
+                self.clauses.len() - 1
+            }
+        };
+        if lbd.is_some() {
+            self.learnts.push(cref)
+        }
+        self.watches[lits[0].idx()].push(Watcher {
+            cref,
+            blocker: lits[1],
+        });
+        self.watches[lits[1].idx()].push(Watcher {
+            cref,
+            blocker: lits[0],
+        });
+        self.clauses[cref].lits = lits;
+        cref
+    }
+
+    /// Value of a literal, if any.
+    fn value(&self, lit: ILit) -> Option<bool> {
+        self.assigns[lit.var()].map(|b| b != lit.is_neg())
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    /// Assigns a literal to true.
+    fn enqueue(&mut self, lit: ILit, reason: Option<usize>) {
+        debug_assert!(self.value(lit).is_none());
+        let var = lit.var();
+        self.assigns[var] = Some(!lit.is_neg());
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Propagates all enqueued literals, returns the conflicting clause if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = self.trail[self.qhead].negate();
+            self.qhead += 1;
+            log::trace!("propagating {}", self.external(false_lit.negate()));
+
+            let mut watchers = std::mem::take(&mut self.watches[false_lit.idx()]);
+            let mut conflict = None;
+            let mut kept = 0;
+            let mut idx = 0;
+
+            'watchers: while idx < watchers.len() {
+                let watcher = watchers[idx];
+                idx += 1;
+                if self.value(watcher.blocker) == Some(true) {
+                    watchers[kept] = watcher;
+                    kept += 1;
+                    continue 'watchers;
+                }
+
+                let cref = watcher.cref;
+                // Make sure the false literal is second.
+                {
+                    let lits = &mut self.clauses[cref].lits;
+                    if lits[0] == false_lit {
+                        lits.swap(0, 1)
+                    }
+                    debug_assert_eq!(lits[1], false_lit);
+                }
+                // If the other watched literal is true, the clause is satisfied.
+                let first = self.clauses[cref].lits[0];
+                let watcher = Watcher {
+                    cref,
+                    blocker: first,
+                };
+                if self.value(first) == Some(true) {
+                    watchers[kept] = watcher;
+                    kept += 1;
+                    continue 'watchers;
+                }
+
+                // Look for a new literal to watch.
+                for k in 2..self.clauses[cref].lits.len() {
+                    let lit = self.clauses[cref].lits[k];
+                    if self.value(lit) != Some(false) {
+                        self.clauses[cref].lits.swap(1, k);
+                        self.watches[lit.idx()].push(watcher);
+                        continue 'watchers;
+                    }
+                }
+
+                // No new watch, clause is unit or conflicting.
+                watchers[kept] = watcher;
+                kept += 1;
+                if self.value(first) == Some(false) {
+                    conflict = Some(cref);
+                    while idx < watchers.len() {
+                        watchers[kept] = watchers[idx];
+                        kept += 1;
+                        idx += 1;
+                    }
+                } else {
+                    self.stats.propagations += 1;
+                    self.enqueue(first, Some(cref))
+                }
+            }
+
+            watchers.truncate(kept);
+            self.watches[false_lit.idx()] = watchers;
+            if conflict.is_some() {
+                self.qhead = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Bumps the activity of a variable.
+    fn bump_var(&mut self, var: usize) {
+        self.activity[var] += self.var_inc;
+        if self.activity[var] > 1e100 {
+            // Rescale to avoid overflows.
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100
+            }
+            self.var_inc *= 1e-100;
+        }
+        self.order.increased(var, &self.activity)
+    }
+
+    /// Bumps the activity of a clause, if it is a learned clause.
+    fn bump_clause(&mut self, cref: usize) {
+        if self.clauses[cref].lbd.is_none() {
+            return;
+        }
+        self.clauses[cref].activity += self.cla_inc;
+        if self.clauses[cref].activity > 1e20 {
+            // Rescale to avoid overflows.
+            for &cref in &self.learnts {
+                self.clauses[cref].activity *= 1e-20
+            }
+            self.cla_inc *= 1e-20;
+        }
+    }
+
+    /// True if a clause is the reason of an assignment, in which case it cannot be deleted.
+    fn locked(&self, cref: usize) -> bool {
+        let first = self.clauses[cref].lits[0];
+        self.value(first) == Some(true) && self.reason[first.var()] == Some(cref)
+    }
+
+    /// Deletes some learned clauses, see [`crate::learned`].
+    ///
+    /// Locked clauses are always kept. Deletions are logged to `proof` (if any).
+    fn reduce_db(&mut self, mut proof: Option<&mut (dyn Proof<Lit> + '_)>) {
+        let (locked, learnts): (Vec<usize>, Vec<usize>) = std::mem::take(&mut self.learnts)
+            .into_iter()
+            .partition(|cref| self.locked(*cref));
+        let learnts = learnts
+            .into_iter()
+            .map(|cref| {
+                let clause = &self.clauses[cref];
+                (cref, clause.lbd.expect("learned clause"), clause.activity)
+            })
+            .collect();
+        let (kept, deleted) = self.reducer.reduce(learnts);
+        self.learnts = kept;
+        self.learnts.extend(locked);
+        if deleted.is_empty() {
+            return;
+        }
+        for &cref in &deleted {
+            let lits = std::mem::take(&mut self.clauses[cref].lits);
+            if let Some(proof) = proof.as_deref_mut() {
+                let lits: Vec<Lit> = lits.iter().map(|lit| self.external(*lit)).collect();
+                proof.delete(&lits)
+            }
+            self.free.push(cref)
+        }
+        self.stats.deleted += deleted.len();
+        let clauses = &self.clauses;
+        for watchers in self.watches.iter_mut() {
+            watchers.retain(|watcher| !clauses[watcher.cref].lits.is_empty())
+        }
+    }
+
+    /// Analyzes a conflict, returns a learned clause and the level to backjump to.
+    ///
+    /// The first literal of the learned clause is the negation of the first UIP, and the second
+    /// one (if any) has the highest decision level among the remaining literals.
+    fn analyze(&mut self, mut confl: usize) -> (Vec<ILit>, usize) {
+        let current_level = self.decision_level();
+        // Placeholder for the asserting literal.
+        let mut learnt = vec![ILit(0)];
+        // Number of literals of the current decision level left to resolve.
+        let mut pending = 0;
+        let mut uip = None;
+        let mut trail_idx = self.trail.len();
+
+        loop {
+            self.bump_clause(confl);
+            let skip = if uip.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[confl].lits.len() {
+                let q = self.clauses[confl].lits[k];
+                let var = q.var();
+                if !self.seen[var] && self.level[var] > 0 {
+                    self.seen[var] = true;
+                    self.bump_var(var);
+                    if self.level[var] >= current_level {
+                        pending += 1
+                    } else {
+                        learnt.push(q)
+                    }
+                }
+            }
+
+            // Next literal of the current level to resolve on.
+            let p = loop {
+                trail_idx -= 1;
+                let p = self.trail[trail_idx];
+                if self.seen[p.var()] {
+                    break p;
+                }
+            };
+            self.seen[p.var()] = false;
+            pending -= 1;
+            uip = Some(p);
+            if pending == 0 {
+                break;
+            }
+            confl =
+                self.reason[p.var()].expect("non-UIP literal of the current level must be implied");
+        }
+        learnt[0] = uip.expect("unreachable").negate();
+
+        // Local minimization: drop literals implied by other literals of the clause.
+        let mut minimized = Vec::with_capacity(learnt.len());
+        minimized.push(learnt[0]);
+        for &lit in &learnt[1..] {
+            let redundant = match self.reason[lit.var()] {
+                None => false,
+                Some(reason) => self.clauses[reason].lits[1..]
+                    .iter()
+                    .all(|q| self.seen[q.var()] || self.level[q.var()] == 0),
+            };
+            if !redundant {
+                minimized.push(lit)
+            }
+        }
+        for lit in &learnt {
+            self.seen[lit.var()] = false
+        }
+        let mut learnt = minimized;
+
+        // Put the literal with the highest level second, it is the one to watch.
+        let backjump_level = if learnt.len() == 1 {
+            0
+        } else {
+            let mut max = 1;
+            for k in 2..learnt.len() {
+                if self.level[learnt[k].var()] > self.level[learnt[max].var()] {
+                    max = k
+                }
+            }
+            learnt.swap(1, max);
+            self.level[learnt[1].var()]
+        };
+
+        (learnt, backjump_level)
+    }
+
+    /// *Literal Block Distance* of a clause, *i.e.* the number of distinct decision levels of its
+    /// (assigned) literals.
+    fn lbd(&self, lits: &[ILit]) -> usize {
+        let mut levels: Vec<usize> = lits.iter().map(|lit| self.level[lit.var()]).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels.len()
+    }
+
+    /// Undoes all assignments above some decision level.
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let lim = self.trail_lim[level];
+        for idx in (lim..self.trail.len()).rev() {
+            let var = self.trail[idx].var();
+            self.polarity[var] = self.assigns[var].expect("trail literals are assigned");
+            self.assigns[var] = None;
+            self.reason[var] = None;
+            self.order.insert(var, &self.activity);
+        }
+        self.trail.truncate(lim);
+        self.trail_lim.truncate(level);
+        self.qhead = lim;
+    }
+
+    /// Picks the next decision, `None` if all variables are assigned.
+    fn pick_branch(&mut self) -> Option<ILit> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.assigns[var].is_none() {
+                return Some(ILit::new(var, !self.polarity[var]));
+            }
+        }
+        None
+    }
+
+    /// Current assignment, as a set of external literals.
+    fn model(&self) -> Set<Lit> {
+        self.trail.iter().map(|lit| self.external(*lit)).collect()
+    }
+
+    /// Runs the CDCL loop.
+    pub fn solve(&mut self) -> Outcome<Lit, ()> {
+        self.solve_with_proof(None)
+    }
+
+    /// Runs the CDCL loop under some assumptions, *i.e.* literals that must be true.
+    ///
+    /// Learned clauses do not depend on the assumptions, they are kept for subsequent calls. On
+    /// unsat results, [`Self::failed_assumptions`] yields the assumptions involved in the conflict.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> Outcome<Lit, ()> {
+        let assumptions = assumptions.iter().map(|lit| self.intern(lit)).collect();
+        self.assumptions = assumptions;
+        let res = self.search(None);
+        self.assumptions.clear();
+        res
+    }
+
+    /// Sets the restart policy of the solver, [`Restarts::Never`] by default.
+    ///
+    /// The state of the previous policy is lost. Restarts keep the learned clauses, and re-decide
+    /// the assumptions (if any).
+    pub fn set_restarts(&mut self, restarts: Restarts) {
+        self.restart = restarts.build()
+    }
+
+    /// Sets the learned clause database settings of the solver, learned clauses are never deleted
+    /// by default.
+    ///
+    /// The state of the previous settings is lost. Clauses that are the reason of an assignment
+    /// are never deleted.
+    pub fn set_reduction(&mut self, reduction: Reduction) {
+        self.reducer = reduction.build()
+    }
+
+    /// Sets the cancellation token of the solver.
+    ///
+    /// Searches yield [`Outcome::Unknown`] when it is cancelled, the solver can still be used
+    /// afterwards. The default token is never cancelled.
+    pub fn set_cancel(&mut self, cancel: Cancel) {
+        self.cancel = cancel
+    }
+
+    /// Statistics accumulated over all the searches of this solver.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Assumptions responsible for the last unsat result.
+    ///
+    /// Empty if the last result was not unsat, or if the formula is unsat regardless of the
+    /// assumptions.
+    pub fn failed_assumptions(&self) -> Set<Lit> {
+        self.conflict
+            .iter()
+            .map(|lit| self.external(*lit))
+            .collect()
+    }
+
+    /// Computes the assumptions responsible for an assumption `p` being false.
+    ///
+    /// Stores the result in `self.conflict`.
+    fn analyze_final(&mut self, p: ILit) {
+        self.conflict.push(p);
+        if self.decision_level() == 0 {
+            return;
+        }
+        self.seen[p.var()] = true;
+        for idx in (self.trail_lim[0]..self.trail.len()).rev() {
+            let lit = self.trail[idx];
+            let var = lit.var();
+            if !self.seen[var] {
+                continue;
+            }
+            match self.reason[var] {
+                // Decisions above level 0 are assumptions at this point.
+                None => self.conflict.push(lit),
+                Some(reason) => {
+                    for k in 1..self.clauses[reason].lits.len() {
+                        let q = self.clauses[reason].lits[k];
+                        if self.level[q.var()] > 0 {
+                            self.seen[q.var()] = true
+                        }
+                    }
+                }
+            }
+            self.seen[var] = false;
+        }
+        self.seen[p.var()] = false;
+    }
+
+    /// Same as [`Self::solve`], but logs a proof of unsatisfiability to `proof` (if any).
+    pub fn solve_with_proof(
+        &mut self,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+    ) -> Outcome<Lit, ()> {
+        let res = self.search(proof.as_deref_mut());
+        if let (Some(proof), true) = (proof, res.is_unsat()) {
+            proof.add(&[])
+        }
+        res
+    }
+
+    /// Logs a learned clause to `proof` (if any).
+    fn log_lemma(&self, proof: Option<&mut (dyn Proof<Lit> + '_)>, lits: &[ILit]) {
+        if let Some(proof) = proof {
+            let lemma: Vec<Lit> = lits.iter().map(|lit| self.external(*lit)).collect();
+            proof.add(&lemma)
+        }
+    }
+
+    /// CDCL loop.
+    fn search(&mut self, mut proof: Option<&mut (dyn Proof<Lit> + '_)>) -> Outcome<Lit, ()> {
+        self.cancel_until(0);
+        self.conflict.clear();
+        if !self.ok {
+            return Outcome::Unsat(());
+        }
+        for idx in 0..self.units.len() {
+            let lit = self.units[idx];
+            match self.value(lit) {
+                Some(true) => (),
+                Some(false) => {
+                    self.ok = false;
+                    return Outcome::Unsat(());
+                }
+                None => {
+                    self.stats.propagations += 1;
+                    self.enqueue(lit, None)
+                }
+            }
+        }
+
+        loop {
+            if self.cancel.is_cancelled() {
+                return Outcome::Unknown;
+            }
+            if let Some(confl) = self.propagate() {
+                log::debug!("conflict at level {}", self.decision_level());
+                self.stats.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return Outcome::Unsat(());
+                }
+                let (learnt, backjump_level) = self.analyze(confl);
+                let lbd = self.lbd(&learnt);
+                self.log_lemma(proof.as_deref_mut(), &learnt);
+                log::debug!("backjumping to level {}", backjump_level);
+                self.stats.learned += 1;
+                // Chronological backtracking would go back to the level right below the current one.
+                self.stats.backjumped += self.decision_level() - backjump_level - 1;
+                self.cancel_until(backjump_level);
+                let lit = learnt[0];
+                self.stats.propagations += 1;
+                if learnt.len() == 1 {
+                    self.enqueue(lit, None)
+                } else {
+                    let cref = self.attach(learnt, Some(lbd));
+                    self.enqueue(lit, Some(cref));
+                    if self.reducer.should_reduce(self.learnts.len()) {
+                        self.reduce_db(proof.as_deref_mut())
+                    }
+                }
+                self.var_inc /= Self::VAR_DECAY;
+                self.cla_inc /= Self::CLAUSE_DECAY;
+                if self.restart.conflict(lbd) {
+                    log::debug!("restarting");
+                    self.stats.restarts += 1;
+                    self.cancel_until(0)
+                }
+            } else {
+                // Assumptions first.
+                let mut next = None;
+                while self.decision_level() < self.assumptions.len() {
+                    let p = self.assumptions[self.decision_level()];
+                    match self.value(p) {
+                        // Already true, open a dummy decision level.
+                        Some(true) => self.trail_lim.push(self.trail.len()),
+                        Some(false) => {
+                            self.analyze_final(p);
+                            return Outcome::Unsat(());
+                        }
+                        None => {
+                            next = Some(p);
+                            break;
+                        }
+                    }
+                }
+                match next.or_else(|| self.pick_branch()) {
+                    None => return Outcome::Sat(self.model()),
+                    Some(lit) => {
+                        log::debug!("deciding {}", self.external(lit));
+                        self.trail_lim.push(self.trail.len());
+                        self.stats.decision(self.decision_level());
+                        self.enqueue(lit, None)
+                    }
+                }
+            }
+        }
+    }
+}
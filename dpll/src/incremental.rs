@@ -0,0 +1,212 @@
+//! Incremental solving, modeled on the [IPASIR] interface.
+//!
+//! A [`Solver`] is fed clauses over time and can be queried several times, under different
+//! *assumptions*: literals that must be true for a given query only.
+//!
+//! [IPASIR]: https://github.com/biotomas/ipasir
+
+use crate::{imperative, prelude::*, unsat_core};
+
+/// Incremental solver.
+///
+/// The [imperative] CDCL variant is incremental: it keeps its state (including learned clauses)
+/// between queries, and handles assumptions as its first decisions. Other variants solve each query
+/// from scratch, assumptions being added as unit clauses. The recursive and iterative
+/// [`Cdcl`](Dpll::Cdcl) variants keep the clauses they learn during queries without assumptions,
+/// the other ones might depend on the assumptions.
+pub struct Solver<Lit: Literal> {
+    dpll: DpllImpl,
+    backend: Backend<Lit>,
+    /// Model produced by the last query, if it was sat.
+    model: Option<Set<Lit>>,
+    /// Assumptions responsible for the last query being unsat.
+    failed: Set<Lit>,
+    /// Cancellation token of the queries.
+    cancel: Cancel,
+}
+
+/// Actual solver behind a [`Solver`].
+enum Backend<Lit: Literal> {
+    Imperative(Box<imperative::Cdcl<Lit>>),
+    /// Solves each query from scratch.
+    Scratch {
+        clauses: Vec<Clause<Lit>>,
+        /// Clauses learned in previous queries.
+        learnt: Vec<Clause<Lit>>,
+        /// Statistics accumulated over previous queries.
+        stats: Stats,
+    },
+}
+
+impl<Lit: Literal> Solver<Lit> {
+    /// Constructor, the formula is initially empty.
+    pub fn new(dpll: DpllImpl) -> Self {
+        let backend = match dpll {
+            DpllImpl::Imperative => {
+                Backend::Imperative(Box::new(imperative::Cdcl::new(Cnf::empty())))
+            }
+            DpllImpl::Recursive(_) | DpllImpl::Iterative(_) => Backend::Scratch {
+                clauses: vec![],
+                learnt: vec![],
+                stats: Stats::new(),
+            },
+        };
+        Self {
+            dpll,
+            backend,
+            model: None,
+            failed: Set::new(),
+            cancel: Cancel::new(),
+        }
+    }
+
+    /// DPLL variant used by this solver.
+    pub fn dpll(&self) -> DpllImpl {
+        self.dpll
+    }
+
+    /// Sets the cancellation token of the queries, which yield [`Outcome::Unknown`] when it is
+    /// cancelled.
+    pub fn set_cancel(&mut self, cancel: Cancel) {
+        if let Backend::Imperative(solver) = &mut self.backend {
+            solver.set_cancel(cancel.clone())
+        }
+        self.cancel = cancel
+    }
+
+    /// Statistics accumulated over all the queries so far.
+    pub fn stats(&self) -> &Stats {
+        match &self.backend {
+            Backend::Imperative(solver) => solver.stats(),
+            Backend::Scratch { stats, .. } => stats,
+        }
+    }
+
+    /// Adds a clause to the formula.
+    pub fn add_clause(&mut self, clause: impl IntoIterator<Item = Lit>) {
+        let clause = Clause::new(clause.into_iter().collect());
+        self.model = None;
+        self.failed.clear();
+        match &mut self.backend {
+            Backend::Imperative(solver) => solver.add_clause(clause),
+            Backend::Scratch { clauses, .. } => clauses.push(clause),
+        }
+    }
+
+    /// Adds all the clauses of a formula.
+    pub fn add_formula<F: Formula<Lit = Lit>>(&mut self, f: F) {
+        for clause in f.into_cnf().into_iter() {
+            self.add_clause(clause.iter().cloned())
+        }
+    }
+
+    /// Checks the satisfiability of the formula.
+    pub fn solve(&mut self) -> Result<Outcome<Lit, ()>, String> {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Checks the satisfiability of the formula under some assumptions.
+    ///
+    /// Assumptions only hold for this query. On unsat results, use [`Self::failed`] to know which
+    /// assumptions were involved. Yields [`Outcome::Unknown`] if the query is cancelled, see
+    /// [`Self::set_cancel`].
+    pub fn solve_with_assumptions(
+        &mut self,
+        assumptions: &[Lit],
+    ) -> Result<Outcome<Lit, ()>, String> {
+        self.model = None;
+        self.failed.clear();
+
+        let outcome = match &mut self.backend {
+            Backend::Imperative(solver) => {
+                let outcome = solver.solve_with_assumptions(assumptions);
+                if outcome.is_unsat() {
+                    self.failed = solver.failed_assumptions()
+                }
+                outcome
+            }
+            Backend::Scratch {
+                clauses,
+                learnt,
+                stats,
+            } => {
+                let mut cnf = Cnf::with_capacity(clauses.len() + learnt.len() + assumptions.len());
+                cnf.extend(clauses.iter().cloned());
+                cnf.extend(learnt.iter().cloned());
+                let base = cnf.len();
+                cnf.extend(assumptions.iter().map(|lit| Clause::new(vec![lit.clone()])));
+
+                if !self.dpll.has_proofs() {
+                    // No core, all assumptions are considered as failed.
+                    let (outcome, query_stats) =
+                        crate::solve(cnf, self.dpll, &Options::new(), &self.cancel)?;
+                    stats.merge(&query_stats);
+                    if outcome.is_unsat() {
+                        self.failed.extend(assumptions.iter().cloned())
+                    }
+                    outcome
+                } else {
+                    let mut trace = unsat_core::Trace::new();
+                    let (outcome, query_stats) = crate::solve_with_core(
+                        cnf,
+                        self.dpll,
+                        &Options::new(),
+                        Some(&mut trace),
+                        &self.cancel,
+                    )?;
+                    stats.merge(&query_stats);
+                    let keeps_lemmas = match self.dpll {
+                        DpllImpl::Recursive(Dpll::Cdcl) | DpllImpl::Iterative(Dpll::Cdcl) => {
+                            assumptions.is_empty()
+                        }
+                        _ => false,
+                    };
+                    if keeps_lemmas {
+                        learnt.extend(
+                            trace
+                                .lemmas()
+                                .filter(|lemma| !lemma.is_empty())
+                                .map(|lemma| Clause::new(lemma.to_vec())),
+                        )
+                    }
+                    outcome.map(
+                        Outcome::Sat,
+                        |core| {
+                            self.failed.extend(
+                                core.into_iter()
+                                    .filter(|idx| *idx >= base)
+                                    .map(|idx| assumptions[idx - base].clone()),
+                            );
+                            Outcome::Unsat(())
+                        },
+                        || Outcome::Unknown,
+                    )
+                }
+            }
+        };
+
+        if let Outcome::Sat(model) = &outcome {
+            self.model = Some(model.clone())
+        }
+        Ok(outcome)
+    }
+
+    /// Value of a literal in the model produced by the last query.
+    ///
+    /// `None` if the last query was not sat, or if the literal's value is irrelevant.
+    pub fn value(&self, lit: &Lit) -> Option<bool> {
+        let model = self.model.as_ref()?;
+        if model.contains(lit) {
+            Some(true)
+        } else if model.contains(&lit.ref_negate()) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// True if `lit` is an assumption of the last query that was involved in making it unsat.
+    pub fn failed(&self, lit: &Lit) -> bool {
+        self.failed.contains(lit)
+    }
+}
@@ -0,0 +1,59 @@
+//! Iterative versions of the [recursive solvers][crate::recursive].
+//!
+//! The rules are the same as in the paper, but recursion is replaced by explicit stacks so that
+//! these versions do not stack overflow. They explore the search space in the exact same order as
+//! their recursive counterparts with the [first-literal heuristic][crate::branch::First], and thus
+//! produce the same outcomes.
+
+use crate::{prelude::*, proof::Proof, stats::Stats};
+
+mod backjump;
+mod cdcl;
+mod plain;
+
+pub use self::{backjump::Backjump, cdcl::Cdcl, plain::Plain};
+
+/// Solves a formula, logging a proof of unsatisfiability to `proof` (if any).
+///
+/// Only the *pure* rule, and the restart policy and learned clause database settings of
+/// [`Dpll::Cdcl`] are taken from `options`, the branching heuristic is ignored. Yields
+/// [`Outcome::Unknown`] if `cancel` is cancelled before the search ends.
+///
+/// Fails if `proof` is not `None` and `dpll` is [`Dpll::Plain`], which does not support proofs, or
+/// the *pure* rule is active.
+pub fn solve<F>(
+    f: F,
+    dpll: Dpll,
+    options: &Options,
+    proof: Option<&mut (dyn Proof<F::Lit> + '_)>,
+    cancel: &Cancel,
+) -> Result<(Outcome<F::Lit, ()>, Stats), String>
+where
+    F: Formula,
+{
+    if let (Dpll::Plain, Some(_)) = (dpll, proof.as_ref()) {
+        return Err(format!("{} does not support proofs", dpll));
+    }
+    if options.pure && proof.is_some() {
+        return Err("the pure rule does not support proofs".into());
+    }
+    let cnf = f.into_cnf();
+    // The *unsat* rules do not support empty clauses in the input.
+    if cnf.iter().any(|clause| clause.is_empty()) {
+        if let Some(proof) = proof {
+            proof.add(&[])
+        }
+        return Ok((Outcome::Unsat(()), Stats::new()));
+    }
+    match dpll {
+        Dpll::Plain => Ok(Plain::new(cnf).with_pure(options.pure).solve(cancel)),
+        Dpll::Backjump => Ok(Backjump::new(cnf)
+            .with_pure(options.pure)
+            .solve_with_proof(proof, cancel)),
+        Dpll::Cdcl => Ok(Cdcl::new(cnf)
+            .with_pure(options.pure)
+            .with_restarts(options.restarts)
+            .with_reduction(options.reduction)
+            .solve_with_proof(proof, cancel)),
+    }
+}
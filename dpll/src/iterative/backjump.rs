@@ -0,0 +1,304 @@
+//! Augments the [`Plain` solver][super::Plain] with backjumping.
+
+use crate::{prelude::*, proof::Proof, stats::Stats};
+
+/// Alias for a map from `Lit`s to sets of `Lit`s.
+pub type Γ<Lit> = Map<Lit, Set<Lit>>;
+
+macro_rules! raise {
+	{ sat $γ:expr } => { return Err(Outcome::Sat($γ)) };
+	{ unsat $deps:expr } => { return Err(Outcome::Unsat($deps)) };
+	{ unknown } => { return Err(Outcome::Unknown) };
+}
+
+pub type Out<Lit> = Outcome<Lit, Set<Lit>>;
+pub type Res<T, Lit> = Result<T, Out<Lit>>;
+
+/// Iterative version of the [recursive backjump solver][crate::recursive::Backjump].
+#[derive(Clone)]
+pub struct Backjump<Lit: Literal> {
+    /// Environment, *i.e.* a set of literals.
+    γ: Γ<Lit>,
+    /// CNF we're working on.
+    δ: LCnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
+}
+
+implem! {
+    impl(Lit: Literal, F: Formula<Lit = Lit>) for Backjump<Lit> {
+        From<F> {
+            |f| Self::new(f),
+        }
+    }
+    impl(Lit: Literal) for Backjump<Lit> {
+        Deref<Target = Γ<Lit>> {
+            |&self| &self.γ,
+            |&mut self| &mut self.γ,
+        }
+    }
+}
+
+impl<Lit: Literal> Backjump<Lit> {
+    /// Construct a naive solver from a formula.
+    pub fn new<F: Formula<Lit = Lit>>(f: F) -> Self {
+        Self {
+            γ: Γ::new(),
+            δ: f.into_cnf().into(),
+            pure: false,
+        }
+    }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+}
+
+impl<Lit: Literal> Backjump<Lit> {
+    /// Checks internal invariants.
+    #[cfg(release)]
+    #[inline]
+    pub fn invariant(&self) {}
+
+    /// Checks internal invariants.
+    #[cfg(not(release))]
+    pub fn invariant(&self) {
+        let γ = &self.γ;
+        for lit in γ.keys() {
+            let nlit = lit.ref_negate();
+            if γ.contains_key(&nlit) {
+                panic!(
+                    "inconsistent environment, contains both {} and {}",
+                    lit, nlit
+                );
+            }
+        }
+    }
+
+    /// *Assume* rule.
+    pub fn assume(&self, lit: Lit, cause: Set<Lit>, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("assume({})", lit);
+        self.invariant();
+        let mut new: Self = self.clone();
+
+        use std::collections::hash_map::Entry::*;
+        match new.entry(lit) {
+            Occupied(mut entry) => {
+                entry.get_mut().extend(cause);
+                Ok(new)
+            }
+            Vacant(entry) => {
+                entry.insert(cause);
+                new.bcp(stats)
+            }
+        }
+    }
+
+    /// *BCP* rule.
+    ///
+    /// See the [plain version][super::Plain::bcp] for details on how the recursion is removed.
+    pub fn bcp(self, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("bcp(), γ.len(): {}", self.γ.len());
+        self.invariant();
+        let Self { mut γ, δ, pure } = self;
+        // Stack of CNFs being simplified, with the index of the next clause to simplify.
+        let mut todo = vec![(δ, 0)];
+        // Clauses simplified so far.
+        let mut new_δ = LCnf::with_capacity(todo[0].0.len());
+        let mut new_clause = Clause::with_capacity(5);
+        let mut new_deps = Set::with_capacity(11);
+
+        'todo: while let Some((δ, mut idx)) = todo.pop() {
+            'conj_iter: while idx < δ.len() {
+                let lclause = &δ[idx];
+                idx += 1;
+                log::trace!("current clause: {}", lclause);
+                new_clause.clear();
+                new_deps.clear();
+                new_deps.extend(lclause.labels().iter().cloned());
+                for lit in lclause.iter() {
+                    if γ.contains_key(lit) {
+                        // Disjunction is true, discard it.
+                        continue 'conj_iter;
+                    } else if let Some(deps) = γ.get(&lit.ref_negate()) {
+                        // Negation of literal is true, ignore literal but remember why it's false.
+                        new_deps.extend(deps.iter().cloned());
+                    } else {
+                        // We know nothing of this literal, keep it.
+                        new_clause.push(lit.clone());
+                    }
+                }
+
+                if new_clause.is_empty() {
+                    stats.conflicts += 1;
+                    raise!(unsat new_deps)
+                } else if new_clause.len() == 1 {
+                    stats.propagations += 1;
+                    let lit = new_clause.drain(0..).next().expect("unreachable");
+                    log::debug!("assume({})", lit);
+                    let mut deps = Set::with_capacity(new_deps.len());
+                    deps.extend(new_deps.drain());
+                    let _prev = γ.insert(lit, deps);
+                    debug_assert!(_prev.is_none());
+                    // Resume this CNF later, start over on the clauses simplified so far.
+                    todo.push((δ, idx));
+                    let simplified = std::mem::replace(&mut new_δ, LCnf::empty());
+                    new_δ = LCnf::with_capacity(simplified.len());
+                    todo.push((simplified, 0));
+                    continue 'todo;
+                } else {
+                    new_δ.push(LClause::new_with(
+                        new_clause.drain(0..).collect(),
+                        new_deps.drain().collect(),
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            γ, δ: new_δ, pure
+        })
+    }
+
+    /// *Pure* rule, see the [recursive version][crate::recursive::Backjump::pure].
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter().map(|lclause| lclause.clause()));
+        if pure.is_empty() {
+            return None;
+        }
+        // Dependencies only mention decisions, and decisions depend on themselves.
+        let deps: Set<Lit> = self.γ.values().flatten().cloned().collect();
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|lclause| !lclause.iter().any(|lit| pure.contains(lit)));
+            for lit in pure {
+                let _prev = new.γ.insert(lit, deps.clone());
+                debug_assert!(_prev.is_none())
+            }
+            pure = crate::pure_literals(new.δ.iter().map(|lclause| lclause.clause()));
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule.
+    ///
+    /// When a conflict makes us explore the second branch of a decision, logs the lemma
+    /// justifying it to `proof` (if any).
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding. Raises an unknown result
+    /// if `cancel` is cancelled before a decision.
+    pub fn unsat(
+        &self,
+        stats: &mut Stats,
+        cancel: &Cancel,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+    ) -> Res<Empty, Lit> {
+        log::debug!("unsat()");
+        // States we made a decision in, with the literal we decided on, `None` once we are in the
+        // second branch of the decision, since a conflict there is just propagated.
+        let mut decisions: Vec<Option<(Self, Lit)>> = Vec::with_capacity(17);
+        let mut current = self.clone();
+
+        loop {
+            current.invariant();
+            if current.δ.is_empty() {
+                raise!(sat current.γ.into_iter().map(|(lit, _)| lit).collect())
+            }
+            if cancel.is_cancelled() {
+                raise!(unknown)
+            }
+            if let Some(new) = current.pure(stats) {
+                current = new;
+                continue;
+            }
+            let lit = match current.δ[0].iter().next() {
+                Some(lit) => lit.clone(),
+                None => panic!("illegal empty disjunct in application of `unsat` rule"),
+            };
+            let mut deps = Set::new();
+            let _is_new = deps.insert(lit.clone());
+            debug_assert!(_is_new);
+            stats.decision(decisions.len() + 1);
+
+            let mut deps = match current.assume(lit.clone(), deps, stats) {
+                Ok(new) => {
+                    decisions.push(Some((current, lit)));
+                    current = new;
+                    continue;
+                }
+                Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                Err(Out::Unsat(deps)) => {
+                    decisions.push(Some((current, lit)));
+                    deps
+                }
+            };
+
+            // Conflict, backjump to the last decision the conflict depends on.
+            current = loop {
+                let (state, lit) = match decisions.pop() {
+                    Some(Some(decision)) => decision,
+                    Some(None) => continue,
+                    None => raise!(unsat deps),
+                };
+
+                log::debug!(
+                    "handling unsat branch with deps:{}",
+                    deps.iter().fold(String::new(), |mut acc, lit| {
+                        acc.push(' ');
+                        acc.push_str(&lit.to_string());
+                        acc
+                    })
+                );
+
+                let lit_was_there = deps.remove(&lit);
+                if !lit_was_there {
+                    stats.backjumped += 1;
+                    continue;
+                }
+                let nlit = lit.ref_negate();
+                if let Some(proof) = proof.as_deref_mut() {
+                    proof.add(&crate::proof::implication(&nlit, &deps))
+                }
+                match state.assume(nlit, deps, stats) {
+                    Ok(new) => {
+                        decisions.push(None);
+                        break new;
+                    }
+                    Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                    Err(Out::Unsat(new_deps)) => deps = new_deps,
+                }
+            };
+        }
+    }
+
+    pub fn solve(&self, cancel: &Cancel) -> (Outcome<Lit, ()>, Stats) {
+        self.solve_with_proof(None, cancel)
+    }
+
+    /// Same as [`Self::solve`], but logs a proof of unsatisfiability to `proof` (if any).
+    pub fn solve_with_proof(
+        &self,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        let mut stats = Stats::new();
+        let res = match self.unsat(&mut stats, cancel, proof.as_deref_mut()) {
+            Err(res) => {
+                if let (Some(proof), true) = (proof, res.is_unsat()) {
+                    proof.add(&[])
+                }
+                res.into_unit_unsat()
+            }
+            Ok(empty) => match empty {},
+        };
+        (res, stats)
+    }
+}
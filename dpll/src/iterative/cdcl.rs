@@ -0,0 +1,450 @@
+//! Augments the [`Backjump` solver][super::Backjump] with CDCL.
+
+use crate::{prelude::*, proof::Proof, recursive::Search, stats::Stats};
+
+/// Alias for a map from `Lit`s to sets of `Lit`s.
+pub type Γ<Lit> = Map<Lit, Set<Lit>>;
+
+macro_rules! raise {
+	{ sat $γ:expr } => { return Err(Outcome::Sat($γ)) };
+	{ unsat $deps:expr } => { return Err(Outcome::Unsat($deps)) };
+	{ unknown } => { return Err(Outcome::Unknown) };
+}
+
+pub type LClauses<Lit> = Set<LClause<Lit>>;
+
+pub type Out<Lit> = Outcome<Lit, (Set<Lit>, LClauses<Lit>)>;
+pub type Res<T, Lit> = Result<T, Out<Lit>>;
+
+/// Iterative version of the [recursive CDCL solver][crate::recursive::Cdcl].
+#[derive(Clone)]
+pub struct Cdcl<Lit: Literal> {
+    /// Environment, *i.e.* a set of literals.
+    γ: Γ<Lit>,
+    /// CNF we're working on.
+    δ: LCnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
+    /// Restart policy.
+    restarts: Restarts,
+    /// Learned clause database settings.
+    reduction: Reduction,
+}
+
+/// A decision on the search stack.
+enum Decision<Lit: Literal> {
+    /// First branch of a decision on `lit`, remembers the state the decision was made in.
+    First { state: Cdcl<Lit>, lit: Lit },
+    /// Second branch of a decision on `lit`, remembers the dependencies and conflict clauses
+    /// produced by the first branch.
+    Second {
+        lit: Lit,
+        deps: Set<Lit>,
+        conflict: LClauses<Lit>,
+    },
+}
+
+implem! {
+    impl(Lit: Literal, F: Formula<Lit = Lit>) for Cdcl<Lit> {
+        From<F> {
+            |f| Self::new(f),
+        }
+    }
+    impl(Lit: Literal) for Cdcl<Lit> {
+        Deref<Target = Γ<Lit>> {
+            |&self| &self.γ,
+            |&mut self| &mut self.γ,
+        }
+    }
+}
+
+impl<Lit: Literal> Cdcl<Lit> {
+    /// Construct a naive solver from a formula.
+    pub fn new<F: Formula<Lit = Lit>>(f: F) -> Self {
+        Self {
+            γ: Γ::new(),
+            δ: f.into_cnf().into(),
+            pure: false,
+            restarts: Restarts::Never,
+            reduction: Reduction::new(),
+        }
+    }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+
+    /// Sets the restart policy, [`Restarts::Never`] by default.
+    pub fn with_restarts(mut self, restarts: Restarts) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Sets the learned clause database settings, learned clauses are never deleted by default.
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+impl<Lit: Literal> Cdcl<Lit> {
+    /// Checks internal invariants.
+    #[cfg(release)]
+    #[inline]
+    pub fn invariant(&self) {}
+
+    /// Checks internal invariants.
+    #[cfg(not(release))]
+    pub fn invariant(&self) {
+        let γ = &self.γ;
+        for lit in γ.keys() {
+            let nlit = lit.ref_negate();
+            if γ.contains_key(&nlit) {
+                panic!(
+                    "inconsistent environment, contains both {} and {}",
+                    lit, nlit
+                );
+            }
+        }
+    }
+
+    /// *Assume* rule.
+    pub fn assume(&self, lit: Lit, cause: Set<Lit>, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("assume({})", lit);
+        self.invariant();
+        let mut new: Self = self.clone();
+
+        use std::collections::hash_map::Entry::*;
+        match new.entry(lit) {
+            Occupied(mut entry) => {
+                entry.get_mut().extend(cause);
+                Ok(new)
+            }
+            Vacant(entry) => {
+                entry.insert(cause);
+                new.bcp(stats)
+            }
+        }
+    }
+
+    /// *BCP* rule.
+    ///
+    /// See the [plain version][super::Plain::bcp] for details on how the recursion is removed.
+    pub fn bcp(self, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("bcp(), γ.len(): {}", self.γ.len());
+        self.invariant();
+        let Self {
+            mut γ,
+            δ,
+            pure,
+            restarts,
+            reduction,
+        } = self;
+        // Stack of CNFs being simplified, with the index of the next clause to simplify.
+        let mut todo = vec![(δ, 0)];
+        // Clauses simplified so far.
+        let mut new_δ = LCnf::with_capacity(todo[0].0.len());
+        let mut new_clause = Clause::with_capacity(5);
+        let mut new_deps = Set::with_capacity(5);
+
+        'todo: while let Some((δ, mut idx)) = todo.pop() {
+            'conj_iter: while idx < δ.len() {
+                let lclause = &δ[idx];
+                idx += 1;
+                log::trace!("current clause: {}", lclause);
+                new_clause.clear();
+                new_deps.clear();
+                new_deps.extend(lclause.labels().iter().cloned());
+                for lit in lclause.iter() {
+                    if γ.contains_key(lit) {
+                        // Disjunction is true, discard it.
+                        continue 'conj_iter;
+                    } else if let Some(deps) = γ.get(&lit.ref_negate()) {
+                        // Negation of literal is true, ignore literal but remember why it's false.
+                        new_deps.extend(deps.iter().cloned());
+                    } else {
+                        // We know nothing of this literal, keep it.
+                        new_clause.push(lit.clone());
+                    }
+                }
+
+                if new_clause.is_empty() {
+                    stats.conflicts += 1;
+                    raise!(unsat(new_deps, LClauses::new()))
+                } else if new_clause.len() == 1 {
+                    stats.propagations += 1;
+                    let lit = new_clause.drain(0..).next().expect("unreachable");
+                    log::debug!("assume({})", lit);
+                    let mut deps = Set::with_capacity(new_deps.len());
+                    deps.extend(new_deps.drain());
+                    let _prev = γ.insert(lit, deps);
+                    debug_assert!(_prev.is_none());
+                    // Resume this CNF later, start over on the clauses simplified so far.
+                    todo.push((δ, idx));
+                    let simplified = std::mem::replace(&mut new_δ, LCnf::empty());
+                    new_δ = LCnf::with_capacity(simplified.len());
+                    todo.push((simplified, 0));
+                    continue 'todo;
+                } else {
+                    new_δ.push(LClause::new_with(
+                        new_clause.drain(0..).collect(),
+                        new_deps.drain().collect(),
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            γ,
+            δ: new_δ,
+            pure,
+            restarts,
+            reduction,
+        })
+    }
+
+    /// *Pure* rule, see the [recursive version][crate::recursive::Cdcl::pure].
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter().map(|lclause| lclause.clause()));
+        if pure.is_empty() {
+            return None;
+        }
+        // Dependencies only mention decisions, and decisions depend on themselves.
+        let deps: Set<Lit> = self.γ.values().flatten().cloned().collect();
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|lclause| !lclause.iter().any(|lit| pure.contains(lit)));
+            for lit in pure {
+                let _prev = new.γ.insert(lit, deps.clone());
+                debug_assert!(_prev.is_none())
+            }
+            pure = crate::pure_literals(new.δ.iter().map(|lclause| lclause.clause()));
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule.
+    ///
+    /// When a conflict makes us explore the second branch of a decision, logs the lemma
+    /// justifying it to `proof` (if any).
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding. Raises an unknown result
+    /// if `search.cancel` is cancelled before a decision, which is made by `search.branch`.
+    ///
+    /// Each conflict leading to a second branch is registered to `search.restart`. When it triggers
+    /// a restart, the search starts over from `self` augmented with the clauses learned so far and
+    /// propagated, restarts stop if one of them does not learn any new clause. Conflict clauses are
+    /// [reduced][crate::recursive::Cdcl::reduce] by `search.reducer` as in the recursive version.
+    pub fn unsat(
+        &self,
+        search: &mut Search<'_, Lit>,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+    ) -> Res<Empty, Lit> {
+        log::debug!("unsat()");
+        let mut decisions: Vec<Decision<Lit>> = Vec::with_capacity(17);
+        // Clauses learned before the last restart.
+        let mut learned = LClauses::new();
+        let mut current = self.clone();
+
+        loop {
+            current.invariant();
+            if current.δ.is_empty() {
+                raise!(sat current.γ.into_iter().map(|(lit, _)| lit).collect())
+            }
+            if search.cancel.is_cancelled() {
+                raise!(unknown)
+            }
+            if let Some(new) = current.pure(&mut search.stats) {
+                current = new;
+                continue;
+            }
+            let lit = search
+                .branch
+                .pick(&mut current.δ.iter().map(|lclause| lclause.clause()));
+            let mut deps = Set::new();
+            let _is_new = deps.insert(lit.clone());
+            debug_assert!(_is_new);
+            search.stats.decision(decisions.len() + 1);
+
+            let (mut deps, mut conflict) =
+                match current.assume(lit.clone(), deps, &mut search.stats) {
+                    Ok(new) => {
+                        decisions.push(Decision::First {
+                            state: current,
+                            lit,
+                        });
+                        current = new;
+                        continue;
+                    }
+                    Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                    Err(Out::Unsat(deps)) => {
+                        decisions.push(Decision::First {
+                            state: current,
+                            lit,
+                        });
+                        deps
+                    }
+                };
+            search
+                .branch
+                .conflict(&deps.iter().map(Literal::ref_negate).collect::<Vec<_>>());
+
+            // Conflict, go up the decisions until one of them can handle it.
+            current = loop {
+                match decisions.pop() {
+                    None => raise!(unsat(deps, conflict)),
+                    Some(Decision::First { state, lit }) => {
+                        conflict = crate::recursive::Cdcl::shift(&lit, &conflict);
+
+                        log::debug!(
+                            "handling unsat branch with deps:{}",
+                            deps.iter().fold(String::new(), |mut acc, lit| {
+                                acc.push(' ');
+                                acc.push_str(&lit.to_string());
+                                acc
+                            })
+                        );
+
+                        let lit_was_there = deps.remove(&lit);
+                        if !lit_was_there {
+                            search.stats.backjumped += 1;
+                            continue;
+                        }
+                        let nlit = lit.ref_negate();
+                        if let Some(proof) = proof.as_deref_mut() {
+                            proof.add(&crate::proof::implication(&nlit, &deps))
+                        }
+                        // See the recursive version for the LBD of the lemma above.
+                        if search.restart.conflict(deps.len() + 1) {
+                            log::debug!("restarting");
+                            conflict.insert(LClause::new_with(Clause::new(vec![nlit]), deps));
+                            search.stats.learned += 1;
+                            let count = learned.len();
+                            learned.extend(Self::unwind(
+                                &mut decisions,
+                                conflict,
+                                &mut search.stats,
+                            ));
+                            search.stats.restarts += 1;
+                            if learned.len() == count {
+                                // See the recursive version.
+                                search.restart = Restarts::Never.build()
+                            }
+                            learned = crate::recursive::Cdcl::reduce(
+                                &mut search.reducer,
+                                learned,
+                                &mut search.stats,
+                            );
+                            let mut root = self.clone();
+                            root.δ.extend(learned.iter().cloned());
+                            // Propagate the learned clauses before deciding.
+                            break root.bcp(&mut search.stats)?;
+                        }
+                        let res = if conflict.is_empty() {
+                            state.assume(nlit, deps.clone(), &mut search.stats)
+                        } else {
+                            let mut new = state;
+                            new.δ.extend(conflict.iter().cloned());
+                            new.assume(nlit, deps.clone(), &mut search.stats)
+                        };
+                        match res {
+                            Ok(new) => {
+                                decisions.push(Decision::Second {
+                                    lit,
+                                    deps,
+                                    conflict,
+                                });
+                                break new;
+                            }
+                            Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                            Err(Out::Unsat((new_deps, new_conflict))) => {
+                                deps = new_deps;
+                                conflict = new_conflict;
+                            }
+                        }
+                    }
+                    Some(Decision::Second {
+                        lit,
+                        deps: lit_deps,
+                        conflict: mut lit_conflict,
+                    }) => {
+                        lit_conflict.extend(conflict);
+                        let conflict_clause =
+                            LClause::new_with(Clause::new(vec![lit.ref_negate()]), lit_deps);
+                        lit_conflict.insert(conflict_clause);
+                        search.stats.learned += 1;
+                        conflict = crate::recursive::Cdcl::reduce(
+                            &mut search.reducer,
+                            lit_conflict,
+                            &mut search.stats,
+                        );
+                    }
+                }
+            };
+        }
+    }
+
+    /// Pops all the decisions, shifting the conflict clauses `learned` accordingly.
+    ///
+    /// Also collects the clauses learned by the decisions in their second branch, so that the
+    /// result is the clauses learned so far, with no dependencies.
+    fn unwind(
+        decisions: &mut Vec<Decision<Lit>>,
+        mut learned: LClauses<Lit>,
+        stats: &mut Stats,
+    ) -> LClauses<Lit> {
+        while let Some(decision) = decisions.pop() {
+            match decision {
+                Decision::First { lit, .. } => {
+                    learned = crate::recursive::Cdcl::shift(&lit, &learned)
+                }
+                Decision::Second {
+                    lit,
+                    deps,
+                    conflict,
+                } => {
+                    // `conflict` is already shifted, and the second branch does not depend on
+                    // `lit`.
+                    learned.extend(conflict);
+                    learned.insert(LClause::new_with(Clause::new(vec![lit.ref_negate()]), deps));
+                    stats.learned += 1;
+                }
+            }
+        }
+        learned
+    }
+
+    pub fn solve(&self, cancel: &Cancel) -> (Outcome<Lit, ()>, Stats) {
+        self.solve_with_proof(None, cancel)
+    }
+
+    /// Same as [`Self::solve`], but logs a proof of unsatisfiability to `proof` (if any).
+    ///
+    /// Decisions are made by the [`First`][crate::branch::First] heuristic.
+    pub fn solve_with_proof(
+        &self,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        let mut branch = crate::branch::First;
+        let mut search = Search::new(cancel, &mut branch, self.restarts, self.reduction);
+        let res = match self.unsat(&mut search, proof.as_deref_mut()) {
+            Err(res) => {
+                if let (Some(proof), true) = (proof, res.is_unsat()) {
+                    proof.add(&[])
+                }
+                res.into_unit_unsat()
+            }
+            Ok(empty) => match empty {},
+        };
+        (res, search.stats)
+    }
+}
@@ -0,0 +1,225 @@
+//! Plain DPLL version, with no optimizations.
+
+use crate::{prelude::*, stats::Stats};
+
+/// Alias for a set of `Lit`.
+pub type Γ<Lit> = Set<Lit>;
+/// Alias for an outcome with no unsat result.
+pub type Out<Lit> = crate::Outcome<Lit, ()>;
+
+macro_rules! raise {
+	{ sat $γ:expr } => { return Err(Out::Sat($γ)) };
+	{ unsat } => { return Err(Out::Unsat(())) };
+	{ unknown } => { return Err(Out::Unknown) };
+}
+
+pub type Res<T, Lit> = Result<T, Out<Lit>>;
+
+/// Iterative version of the [recursive plain solver][crate::recursive::Plain].
+#[derive(Clone)]
+pub struct Plain<Lit: Literal> {
+    /// Environment, *i.e.* a set of literals.
+    γ: Γ<Lit>,
+    /// CNF we're working on.
+    δ: Cnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
+}
+
+implem! {
+    impl(Lit: Literal, F: Formula<Lit = Lit>) for Plain<Lit> {
+        From<F> {
+            |f| Self::new(f),
+        }
+    }
+    impl(Lit: Literal) for Plain<Lit> {
+        Deref<Target = Γ<Lit>> {
+            |&self| &self.γ,
+            |&mut self| &mut self.γ,
+        }
+    }
+}
+
+impl<Lit: Literal> Plain<Lit> {
+    /// Construct a naive solver from a formula.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Formula<Lit = Lit>,
+    {
+        Self {
+            γ: Γ::new(),
+            δ: f.into_cnf(),
+            pure: false,
+        }
+    }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+}
+
+impl<Lit: Literal> Plain<Lit> {
+    /// *Assume* rule.
+    pub fn assume(&self, lit: Lit, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("assume({})", lit);
+        let mut new: Self = self.clone();
+        let is_new = new.insert(lit);
+
+        if is_new {
+            new.bcp(stats)
+        } else {
+            panic!("trying to assume a literal twice")
+        }
+    }
+
+    /// *BCP* rule.
+    ///
+    /// In the recursive version, finding a unit clause triggers an *assume* on the CNF simplified
+    /// so far, and the simplification then resumes on the remaining clauses. Here, the clauses
+    /// left to simplify are stored on a stack instead.
+    pub fn bcp(self, stats: &mut Stats) -> Res<Self, Lit> {
+        log::debug!("bcp(), γ.len(): {}", self.γ.len());
+        let Self { mut γ, δ, pure } = self;
+        // Stack of CNFs being simplified, with the index of the next clause to simplify.
+        let mut todo = vec![(δ, 0)];
+        // Clauses simplified so far.
+        let mut new_δ = Cnf::with_capacity(todo[0].0.len());
+        let mut new_clause = Clause::with_capacity(5);
+
+        'todo: while let Some((δ, mut idx)) = todo.pop() {
+            'conj_iter: while idx < δ.len() {
+                let disj = &δ[idx];
+                idx += 1;
+                new_clause.clear();
+                for lit in disj.iter() {
+                    if γ.contains(lit) {
+                        // Disjunction is true, discard it.
+                        continue 'conj_iter;
+                    } else if γ.contains(&lit.ref_negate()) {
+                        // Negation of literal is true, ignore literal (do nothing and continue).
+                    } else {
+                        // We know nothing of this literal, keep it.
+                        new_clause.push(lit.clone());
+                    }
+                }
+
+                match new_clause.len() {
+                    0 => {
+                        stats.conflicts += 1;
+                        raise!(unsat)
+                    }
+                    1 => {
+                        stats.propagations += 1;
+                        let lit = new_clause.drain(0..).next().expect("unreachable");
+                        log::debug!("assume({})", lit);
+                        let is_new = γ.insert(lit);
+                        debug_assert!(is_new);
+                        // Resume this CNF later, start over on the clauses simplified so far.
+                        todo.push((δ, idx));
+                        let simplified = std::mem::replace(&mut new_δ, Cnf::empty());
+                        new_δ = Cnf::with_capacity(simplified.len());
+                        todo.push((simplified, 0));
+                        continue 'todo;
+                    }
+                    _ => {
+                        // Got a new disjunction, add it to the new CNF.
+                        new_clause.shrink_to_fit();
+                        new_δ.push(new_clause.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            γ, δ: new_δ, pure
+        })
+    }
+
+    /// *Pure* rule, see the [recursive version][crate::recursive::Plain::pure].
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter());
+        if pure.is_empty() {
+            return None;
+        }
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|clause| !clause.iter().any(|lit| pure.contains(lit)));
+            new.γ.extend(pure);
+            pure = crate::pure_literals(new.δ.iter());
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule, raises an unknown result if `cancel` is cancelled before a decision.
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding.
+    pub fn unsat(&self, stats: &mut Stats, cancel: &Cancel) -> Res<Empty, Lit> {
+        log::debug!("unsat()");
+        // States we made a decision in, with the literal we decided on, `None` once we are in the
+        // second branch of the decision, since a conflict there is just propagated.
+        let mut decisions: Vec<Option<(Self, Lit)>> = Vec::with_capacity(17);
+        let mut current = self.clone();
+
+        loop {
+            if current.δ.is_empty() {
+                raise!(sat current.γ)
+            }
+            if cancel.is_cancelled() {
+                raise!(unknown)
+            }
+            if let Some(new) = current.pure(stats) {
+                current = new;
+                continue;
+            }
+            let lit = match current.δ[0].iter().next() {
+                Some(lit) => lit.clone(),
+                None => panic!("illegal empty disjunct in application of `unsat` rule"),
+            };
+            stats.decision(decisions.len() + 1);
+            match current.assume(lit.clone(), stats) {
+                Ok(new) => {
+                    decisions.push(Some((current, lit)));
+                    current = new;
+                    continue;
+                }
+                Err(e) if e.is_sat() => return Err(e),
+                Err(_) => decisions.push(Some((current, lit))),
+            }
+
+            // Conflict, backtrack to the last decision with an unexplored second branch.
+            current = loop {
+                let (state, lit) = match decisions.pop() {
+                    Some(Some(decision)) => decision,
+                    Some(None) => continue,
+                    None => raise!(unsat),
+                };
+                log::trace!("backtracking {}", lit);
+                match state.assume(lit.ref_negate(), stats) {
+                    Ok(new) => {
+                        decisions.push(None);
+                        break new;
+                    }
+                    Err(e) if e.is_sat() => return Err(e),
+                    Err(_) => continue,
+                }
+            };
+        }
+    }
+
+    pub fn solve(&self, cancel: &Cancel) -> (Out<Lit>, Stats) {
+        let mut stats = Stats::new();
+        let res = match self.unsat(&mut stats, cancel) {
+            Err(res) => res,
+            Ok(empty) => match empty {},
+        };
+        (res, stats)
+    }
+}
@@ -0,0 +1,107 @@
+//! Learned clause database management of the CDCL solvers, see [`DpllImpl::has_reduction`].
+//!
+//! Learned clauses are redundant: they speed up the search but can be deleted at any time. Keeping
+//! all of them makes memory and propagation costs grow without bound, so the database is
+//! periodically *reduced* once it holds too many clauses. Reductions delete the worst half of the
+//! learned clauses, except *glue* clauses which are always kept.
+//!
+//! Clauses are scored by their *Literal Block Distance* (LBD), *i.e.* the number of distinct
+//! decision levels among their literals when they are learned, and then by their activity. Low LBD
+//! clauses tend to propagate often, and the activity of a clause increases when it takes part in
+//! conflicts and decays over time. The [recursive][crate::recursive] and
+//! [iterative][crate::iterative] solvers do not track activities, see
+//! [`recursive::Cdcl::reduce`](crate::recursive::Cdcl::reduce).
+
+use std::cmp::Ordering;
+
+use crate::prelude::*;
+
+/// Settings of the learned clause database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reduction {
+    /// Number of learned clauses triggering the first reduction, `None` to never delete learned
+    /// clauses as in the paper.
+    pub max: Option<usize>,
+    /// Learned clauses with an LBD lower than or equal to `glue` are never deleted.
+    pub glue: usize,
+}
+impl Default for Reduction {
+    fn default() -> Self {
+        Self {
+            max: None,
+            glue: Self::DEFAULT_GLUE,
+        }
+    }
+}
+impl Reduction {
+    /// Default [`Self::glue`], clauses linking two decision levels as in glucose.
+    pub const DEFAULT_GLUE: usize = 2;
+
+    /// Default settings, learned clauses are never deleted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the reducer.
+    pub fn build(self) -> Reducer {
+        Reducer::new(self)
+    }
+}
+
+/// State of the reduction of a learned clause database.
+#[derive(Debug, Clone)]
+pub struct Reducer {
+    /// Number of learned clauses triggering the next reduction.
+    max: Option<usize>,
+    glue: usize,
+}
+impl Reducer {
+    /// Growth factor of the maximum number of learned clauses after each reduction.
+    const GROWTH: f64 = 1.1;
+
+    /// Constructor.
+    pub fn new(reduction: Reduction) -> Self {
+        Self {
+            max: reduction.max,
+            glue: reduction.glue,
+        }
+    }
+
+    /// True if a database of `count` learned clauses should be reduced.
+    pub fn should_reduce(&self, count: usize) -> bool {
+        matches!(self.max, Some(max) if count >= max)
+    }
+
+    /// Reduces some learned clauses, yields the clauses kept and the clauses deleted.
+    ///
+    /// Each clause comes with its LBD and activity. The best half of the clauses is kept along
+    /// with the glue clauses, ties are broken by the order of `learned`. The number of clauses
+    /// triggering the next reduction grows.
+    pub fn reduce<T>(&mut self, mut learned: Vec<(T, usize, f64)>) -> (Vec<T>, Vec<T>) {
+        // Best clauses first, the sort is stable.
+        learned.sort_by(|(_, lbd_1, act_1), (_, lbd_2, act_2)| {
+            lbd_1
+                .cmp(lbd_2)
+                .then(act_2.partial_cmp(act_1).unwrap_or(Ordering::Equal))
+        });
+        let keep = learned.len() - learned.len() / 2;
+        let mut kept = Vec::with_capacity(keep);
+        let mut deleted = Vec::with_capacity(learned.len() - keep);
+        for (idx, (clause, lbd, _)) in learned.into_iter().enumerate() {
+            if idx < keep || lbd <= self.glue {
+                kept.push(clause)
+            } else {
+                deleted.push(clause)
+            }
+        }
+        log::debug!(
+            "reduced learned clauses, kept {}, deleted {}",
+            kept.len(),
+            deleted.len()
+        );
+        self.max = self
+            .max
+            .map(|max| ((max as f64 * Self::GROWTH) as usize).max(max + 1));
+        (kept, deleted)
+    }
+}
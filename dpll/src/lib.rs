@@ -8,20 +8,136 @@ use std::iter::FromIterator;
 pub mod prelude {
     pub use base::prelude::{implem, *};
 
-    pub use crate::{Clause, Cnf, Dpll, DpllImpl, Formula, LClause, LCnf, Literal, Outcome};
+    pub use crate::{
+        branch::Branching, cancel::Cancel, learned::Reduction, restart::Restarts, stats::Stats,
+        Clause, Cnf, Dpll, DpllImpl, Formula, LClause, LCnf, Literal, Options, Outcome,
+    };
 }
 
 use prelude::*;
 
+pub mod branch;
+pub mod cancel;
+pub mod expr;
+pub mod imperative;
+pub mod incremental;
+pub mod iterative;
+pub mod learned;
+pub mod preprocess;
+pub mod proof;
 pub mod recursive;
+pub mod restart;
+pub mod rng;
+pub mod stats;
+pub mod unsat_core;
 
-pub fn solve<F: Formula>(f: F, dpll: DpllImpl) -> Result<Outcome<F::Lit, ()>, String> {
+/// Options of a DPLL run, see [`solve`].
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Branching heuristic, see [`branch`] and [`DpllImpl::has_branching`].
+    pub branching: Branching,
+    /// Activates the *pure* rule, see [`DpllImpl::has_pure`].
+    pub pure: bool,
+    /// Restart policy, see [`restart`] and [`DpllImpl::has_restarts`].
+    pub restarts: Restarts,
+    /// Learned clause database settings, see [`learned`] and [`DpllImpl::has_reduction`].
+    pub reduction: Reduction,
+}
+impl Options {
+    /// Default options, *i.e.* the rules of the paper.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Solves a formula, yields the outcome along with the statistics of the run.
+///
+/// Decisions are made by the branching heuristic of `options`, see [`branch`]. The search stops
+/// with [`Outcome::Unknown`] when `cancel` is cancelled, see [`cancel`].
+///
+/// Fails if `dpll` does not support `options`: if the heuristic is not [`Branching::First`] and
+/// `dpll` does not support branching heuristics, see [`DpllImpl::has_branching`], if the *pure*
+/// rule is active and `dpll` does not support it, see [`DpllImpl::has_pure`], if the restart policy
+/// is not [`Restarts::Never`] and `dpll` does not restart, see [`DpllImpl::has_restarts`], or if
+/// learned clauses can be deleted and `dpll` does not support it, see
+/// [`DpllImpl::has_reduction`].
+pub fn solve<F: Formula>(
+    f: F,
+    dpll: DpllImpl,
+    options: &Options,
+    cancel: &Cancel,
+) -> Result<(Outcome<F::Lit, ()>, Stats), String> {
+    solve_with_proof(f, dpll, options, None, cancel)
+}
+
+/// Same as [`solve`], but logs a proof of unsatisfiability to `proof` (if any).
+///
+/// Fails if `proof` is not `None` and `dpll` does not support proofs, see [`DpllImpl::has_proofs`],
+/// or the *pure* rule is active: lemmas relying on pure literals are not implied by the formula.
+pub fn solve_with_proof<F: Formula>(
+    f: F,
+    dpll: DpllImpl,
+    options: &Options,
+    proof: Option<&mut (dyn proof::Proof<F::Lit> + '_)>,
+    cancel: &Cancel,
+) -> Result<(Outcome<F::Lit, ()>, Stats), String> {
     use self::DpllImpl::*;
+    if options.branching != Branching::First && !dpll.has_branching() {
+        return Err(format!("{} does not support branching heuristics", dpll));
+    }
+    if options.pure && !dpll.has_pure() {
+        return Err(format!("{} does not support the pure rule", dpll));
+    }
+    if options.restarts != Restarts::Never && !dpll.has_restarts() {
+        return Err(format!("{} does not support restarts", dpll));
+    }
+    if options.reduction.max.is_some() && !dpll.has_reduction() {
+        return Err(format!("{} does not support learned clause deletion", dpll));
+    }
     match dpll {
-        Recursive(dpll) => recursive::solve(f, dpll),
+        Recursive(dpll) => recursive::solve(f, dpll, options, proof, cancel),
+        Iterative(dpll) => iterative::solve(f, dpll, options, proof, cancel),
+        Imperative => imperative::solve(f, options, proof, cancel),
     }
 }
 
+/// Result of [`solve_with_core`], an outcome carrying the unsat core on unsat results.
+pub type CoreResult<Lit> = Result<(Outcome<Lit, Vec<usize>>, Stats), String>;
+
+/// Same as [`solve_with_proof`], but yields an unsat core on unsat results.
+///
+/// The core is given as the (sorted) indices of clauses of the CNF of `f`, see [`unsat_core`]. Fails
+/// if `dpll` does not support proofs, see [`DpllImpl::has_proofs`].
+pub fn solve_with_core<F: Formula>(
+    f: F,
+    dpll: DpllImpl,
+    options: &Options,
+    proof: Option<&mut (dyn proof::Proof<F::Lit> + '_)>,
+    cancel: &Cancel,
+) -> CoreResult<F::Lit> {
+    if !dpll.has_proofs() {
+        return Err(format!("{} does not support unsat cores", dpll));
+    }
+    let cnf = f.into_cnf();
+    let mut trace = unsat_core::Trace::new();
+    let (outcome, stats) = match proof {
+        None => solve_with_proof(cnf.clone(), dpll, options, Some(&mut trace), cancel)?,
+        Some(proof) => solve_with_proof(
+            cnf.clone(),
+            dpll,
+            options,
+            Some(&mut (&mut trace, proof)),
+            cancel,
+        )?,
+    };
+    let outcome = match outcome {
+        Outcome::Sat(model) => Outcome::Sat(model),
+        Outcome::Unsat(()) => Outcome::Unsat(unsat_core::extract(&cnf, &trace)?),
+        Outcome::Unknown => Outcome::Unknown,
+    };
+    Ok((outcome, stats))
+}
+
 /// Enumerates DPLL variations.
 #[derive(Debug, Clone, Copy)]
 pub enum Dpll {
@@ -69,12 +185,18 @@ implem! {
 pub enum DpllImpl {
     /// Recursive implementation.
     Recursive(Dpll),
+    /// Iterative implementation.
+    Iterative(Dpll),
+    /// Imperative CDCL implementation, not derived from the paper.
+    Imperative,
 }
 implem! {
     for DpllImpl {
         Display {
             |&self, fmt| match self {
                 Self::Recursive(dpll) => write!(fmt, "recursive DPLL {}", dpll),
+                Self::Iterative(dpll) => write!(fmt, "iterative DPLL {}", dpll),
+                Self::Imperative => "imperative CDCL".fmt(fmt),
             }
         }
     }
@@ -85,17 +207,92 @@ impl Default for DpllImpl {
     }
 }
 impl DpllImpl {
-    pub const NAMES: &'static [(&'static str, &'static str)] = &[(
-        "recursive",
-        "Recursive implementation (might stack overflow)",
-    )];
+    pub const NAMES: &'static [(&'static str, &'static str)] = &[
+        (
+            "recursive",
+            "Recursive implementation (might stack overflow)",
+        ),
+        (
+            "iterative",
+            "Iterative implementation, same as `recursive` but with explicit stacks",
+        ),
+        (
+            "imperative",
+            "Imperative CDCL with a trail and two watched literals, only supports `cdcl`",
+        ),
+    ];
+    /// All DPLL variants, in the order the `all` mode runs them.
+    pub const ALL: &'static [Self] = &[
+        Self::Recursive(Dpll::Plain),
+        Self::Recursive(Dpll::Backjump),
+        Self::Recursive(Dpll::Cdcl),
+        Self::Iterative(Dpll::Plain),
+        Self::Iterative(Dpll::Backjump),
+        Self::Iterative(Dpll::Cdcl),
+        Self::Imperative,
+    ];
+    /// True if this implementation can produce proofs of unsatisfiability.
+    pub fn has_proofs(self) -> bool {
+        match self {
+            Self::Recursive(Dpll::Plain) | Self::Iterative(Dpll::Plain) => false,
+            Self::Recursive(_) | Self::Iterative(_) | Self::Imperative => true,
+        }
+    }
+    /// True if this implementation supports branching heuristics other than
+    /// [`Branching::First`].
+    pub fn has_branching(self) -> bool {
+        match self {
+            Self::Recursive(_) => true,
+            Self::Iterative(_) | Self::Imperative => false,
+        }
+    }
+    /// True if this implementation supports the *pure* rule.
+    pub fn has_pure(self) -> bool {
+        match self {
+            Self::Recursive(_) | Self::Iterative(_) => true,
+            Self::Imperative => false,
+        }
+    }
+    /// True if this implementation supports restart policies, *i.e.* if it is a CDCL variant.
+    pub fn has_restarts(self) -> bool {
+        match self {
+            Self::Recursive(Dpll::Cdcl) | Self::Iterative(Dpll::Cdcl) | Self::Imperative => true,
+            Self::Recursive(_) | Self::Iterative(_) => false,
+        }
+    }
+    /// True if this implementation can delete learned clauses, *i.e.* if it is a CDCL variant.
+    pub fn has_reduction(self) -> bool {
+        match self {
+            Self::Recursive(Dpll::Cdcl) | Self::Iterative(Dpll::Cdcl) | Self::Imperative => true,
+            Self::Recursive(_) | Self::Iterative(_) => false,
+        }
+    }
+    /// Short name of this implementation, `<implementation>/<dpll>` as on the command line.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::Recursive(Dpll::Plain) => "recursive/plain",
+            Self::Recursive(Dpll::Backjump) => "recursive/backjump",
+            Self::Recursive(Dpll::Cdcl) => "recursive/cdcl",
+            Self::Iterative(Dpll::Plain) => "iterative/plain",
+            Self::Iterative(Dpll::Backjump) => "iterative/backjump",
+            Self::Iterative(Dpll::Cdcl) => "iterative/cdcl",
+            Self::Imperative => "imperative/cdcl",
+        }
+    }
+
     pub fn from_name(name: &str, sub_name: Option<&str>) -> Option<Self> {
+        let dpll = || {
+            sub_name
+                .map(|sub_name| Dpll::from_name(sub_name))
+                .unwrap_or_else(|| Some(Dpll::default()))
+        };
         match name.as_ref() {
-            "recursive" => Some(Self::Recursive(
-                sub_name
-                    .map(|sub_name| Dpll::from_name(sub_name))
-                    .unwrap_or_else(|| Some(Dpll::default()))?,
-            )),
+            "recursive" => Some(Self::Recursive(dpll()?)),
+            "iterative" => Some(Self::Iterative(dpll()?)),
+            "imperative" => match dpll()? {
+                Dpll::Cdcl => Some(Self::Imperative),
+                Dpll::Plain | Dpll::Backjump => None,
+            },
             _ => None,
         }
     }
@@ -108,6 +305,8 @@ pub enum Outcome<Lit, UnsatRes> {
     Sat(Set<Lit>),
     /// Unsat result.
     Unsat(UnsatRes),
+    /// The search was cancelled before reaching a result, see [`cancel`].
+    Unknown,
 }
 impl<Lit, UnsatRes> Outcome<Lit, UnsatRes> {
     /// Sat constructor.
@@ -123,43 +322,58 @@ impl<Lit, UnsatRes> Outcome<Lit, UnsatRes> {
     pub fn is_sat(&self) -> bool {
         match self {
             Self::Sat(_) => true,
-            Self::Unsat(_) => false,
+            Self::Unsat(_) | Self::Unknown => false,
         }
     }
-    /// True if the outcome is sat.
+    /// True if the outcome is unsat.
     pub fn is_unsat(&self) -> bool {
         match self {
             Self::Unsat(_) => true,
-            Self::Sat(_) => false,
+            Self::Sat(_) | Self::Unknown => false,
+        }
+    }
+    /// True if the outcome is unknown.
+    pub fn is_unknown(&self) -> bool {
+        match self {
+            Self::Unknown => true,
+            Self::Sat(_) | Self::Unsat(_) => false,
         }
     }
 
-    /// Map over either the [`Self::Sat`] or [`Self::Unsat`] variant.
+    /// Map over either the [`Self::Sat`], [`Self::Unsat`] or [`Self::Unknown`] variant.
     pub fn map<T>(
         self,
         sat_action: impl FnOnce(Set<Lit>) -> T,
         unsat_action: impl FnOnce(UnsatRes) -> T,
+        unknown_action: impl FnOnce() -> T,
     ) -> T {
         match self {
             Self::Sat(γ) => sat_action(γ),
             Self::Unsat(res) => unsat_action(res),
+            Self::Unknown => unknown_action(),
         }
     }
-    /// Map over either the [`Self::Sat`] or [`Self::Unsat`] variant.
+    /// Map over either the [`Self::Sat`], [`Self::Unsat`] or [`Self::Unknown`] variant.
     pub fn map_ref<T>(
         &self,
         sat_action: impl FnOnce(&Set<Lit>) -> T,
         unsat_action: impl FnOnce(&UnsatRes) -> T,
+        unknown_action: impl FnOnce() -> T,
     ) -> T {
         match self {
             Self::Sat(γ) => sat_action(γ),
             Self::Unsat(res) => unsat_action(res),
+            Self::Unknown => unknown_action(),
         }
     }
 
     /// Erases the `UnsatRes` data and replaces it by unit.
     pub fn into_unit_unsat(self) -> Outcome<Lit, ()> {
-        self.map(|sat| Outcome::Sat(sat), |_| Outcome::Unsat(()))
+        self.map(
+            |sat| Outcome::Sat(sat),
+            |_| Outcome::Unsat(()),
+            || Outcome::Unknown,
+        )
     }
 }
 
@@ -280,6 +494,13 @@ impl<Lit: Literal> Cnf<Lit> {
     pub fn into_iter(self) -> std::vec::IntoIter<Clause<Lit>> {
         self.clauses.into_iter()
     }
+
+    /// Index of the first clause that has no literal in `model`, if any.
+    pub fn first_falsified(&self, model: &Set<Lit>) -> Option<usize> {
+        self.clauses
+            .iter()
+            .position(|clause| !clause.iter().any(|lit| model.contains(lit)))
+    }
 }
 implem! {
     impl(Lit: Literal) for Cnf<Lit> {
@@ -290,6 +511,20 @@ implem! {
     }
 }
 
+/// Pure literals of some clauses, *i.e.* literals whose negation does not appear in the clauses.
+pub fn pure_literals<'a, Lit: Literal + 'a>(
+    clauses: impl IntoIterator<Item = &'a Clause<Lit>>,
+) -> Set<Lit> {
+    let mut lits = Set::new();
+    for clause in clauses {
+        lits.extend(clause.iter().cloned())
+    }
+    lits.iter()
+        .filter(|lit| !lits.contains(&lit.ref_negate()))
+        .cloned()
+        .collect()
+}
+
 /// A labelled Clause.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LClause<Lit: Literal> {
@@ -0,0 +1,607 @@
+//! CNF preprocessing.
+//!
+//! [`preprocess`] simplifies a CNF before it is solved. Most simplifications yield an equivalent
+//! CNF: tautology and duplicate removal, unit propagation, subsumption, self-subsuming resolution
+//! and failed literal probing. *Bounded variable elimination* (BVE) only preserves
+//! satisfiability: the clauses it removes are remembered so that [`Preprocessed::reconstruct`] can
+//! turn a model of the preprocessed CNF into a model of the original one.
+
+use std::collections::BTreeSet;
+
+use crate::prelude::*;
+
+/// Preprocesses a formula.
+pub fn preprocess<F: Formula>(f: F) -> Preprocessed<F::Lit> {
+    Preprocessor::new(f.into_cnf()).run()
+}
+
+/// Statistics of a preprocessing run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of tautologies removed.
+    pub tautologies: usize,
+    /// Number of duplicate clauses removed.
+    pub duplicates: usize,
+    /// Number of literals fixed by unit propagation, including failed literals.
+    pub fixed: usize,
+    /// Number of clauses removed because they were subsumed.
+    pub subsumed: usize,
+    /// Number of literals removed by self-subsuming resolution.
+    pub strengthened: usize,
+    /// Number of failed literals found by probing.
+    pub failed: usize,
+    /// Number of variables eliminated.
+    pub eliminated: usize,
+}
+implem! {
+    for Stats {
+        Display {
+            |&self, fmt| write!(
+                fmt,
+                "tautologies: {}, duplicates: {}, fixed: {}, subsumed: {}, strengthened: {}, \
+                failed literals: {}, eliminated variables: {}",
+                self.tautologies,
+                self.duplicates,
+                self.fixed,
+                self.subsumed,
+                self.strengthened,
+                self.failed,
+                self.eliminated,
+            )
+        }
+    }
+}
+
+/// Result of preprocessing a CNF.
+#[derive(Debug, Clone)]
+pub struct Preprocessed<Lit: Literal> {
+    /// Preprocessed CNF, a single empty clause if preprocessing proved the CNF unsat.
+    cnf: Cnf<Lit>,
+    /// Clauses removed by BVE and unit clauses, in the order they were removed, with the literal
+    /// that makes them true.
+    witnesses: Vec<(Lit, Clause<Lit>)>,
+    stats: Stats,
+}
+impl<Lit: Literal> Preprocessed<Lit> {
+    /// Preprocessed CNF.
+    pub fn cnf(&self) -> &Cnf<Lit> {
+        &self.cnf
+    }
+    /// Statistics of the preprocessing run.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Turns a model of the preprocessed CNF into a model of the original one.
+    ///
+    /// Removed clauses are replayed in reverse order, making their witness literal true when they
+    /// are not satisfied.
+    pub fn reconstruct(&self, mut model: Set<Lit>) -> Set<Lit> {
+        // Replaying is only sound on total assignments, the variables of removed clauses the model
+        // does not mention get an arbitrary value.
+        for (_, clause) in &self.witnesses {
+            for lit in clause.iter() {
+                if !model.contains(lit) && !model.contains(&lit.ref_negate()) {
+                    let _ = model.insert(lit.ref_negate());
+                }
+            }
+        }
+        for (witness, clause) in self.witnesses.iter().rev() {
+            if !clause.iter().any(|lit| model.contains(lit)) {
+                let _ = model.remove(&witness.ref_negate());
+                let _ = model.insert(witness.clone());
+            }
+        }
+        model
+    }
+}
+
+/// Checks whether `c` subsumes `d`, possibly after resolution.
+///
+/// Yields `Some(None)` if `c` subsumes `d`, and `Some(Some(lit))` if the resolvent of `c` and `d`
+/// on `lit` subsumes `d`, in which case `lit` can be removed from `d`.
+fn subsumes<Lit: Literal>(c: &Clause<Lit>, d: &Clause<Lit>) -> Option<Option<Lit>> {
+    let d = d.iter().as_slice();
+    let mut removed = None;
+    for lit in c.iter() {
+        if d.binary_search(lit).is_ok() {
+            continue;
+        }
+        let nlit = lit.ref_negate();
+        if removed.is_none() && d.binary_search(&nlit).is_ok() {
+            removed = Some(nlit)
+        } else {
+            return None;
+        }
+    }
+    Some(removed)
+}
+
+/// Resolvent of `pos` and `neg` on `var`, `None` if it is a tautology.
+fn resolve<Lit: Literal>(pos: &Clause<Lit>, neg: &Clause<Lit>, var: &Lit) -> Option<Vec<Lit>> {
+    let nvar = var.ref_negate();
+    let mut lits: Vec<Lit> = pos.iter().filter(|lit| *lit != var).cloned().collect();
+    for lit in neg.iter().filter(|lit| **lit != nvar) {
+        if lits.contains(&lit.ref_negate()) {
+            return None;
+        } else if !lits.contains(lit) {
+            lits.push(lit.clone())
+        }
+    }
+    Some(lits)
+}
+
+/// Preprocessing state.
+///
+/// Clauses are stored by index, with occurrence lists mapping literals to the clauses they appear
+/// in. Occurrence lists are ordered so that preprocessing is deterministic.
+struct Preprocessor<Lit: Literal> {
+    /// Clauses, `None` for removed ones.
+    clauses: Vec<Option<Clause<Lit>>>,
+    /// Indices of the clauses each literal appears in.
+    occs: Map<Lit, BTreeSet<usize>>,
+    /// Literals fixed by unit propagation.
+    fixed: Set<Lit>,
+    /// Units left to propagate.
+    units: Vec<Lit>,
+    /// Clauses left to check for backward subsumption.
+    touched: Vec<usize>,
+    /// See [`Preprocessed::witnesses`].
+    witnesses: Vec<(Lit, Clause<Lit>)>,
+    /// True if the empty clause was derived.
+    unsat: bool,
+    stats: Stats,
+}
+impl<Lit: Literal> Preprocessor<Lit> {
+    /// Variables with more occurrences than this are not eliminated.
+    const ELIM_OCC_LIMIT: usize = 20;
+    /// Variables producing resolvents longer than this are not eliminated.
+    const ELIM_RESOLVENT_LIMIT: usize = 20;
+    /// Maximum number of literals probed.
+    const PROBE_LIMIT: usize = 1000;
+
+    fn new(cnf: Cnf<Lit>) -> Self {
+        let mut res = Self {
+            clauses: Vec::with_capacity(cnf.len()),
+            occs: Map::new(),
+            fixed: Set::new(),
+            units: vec![],
+            touched: Vec::with_capacity(cnf.len()),
+            witnesses: vec![],
+            unsat: false,
+            stats: Stats::default(),
+        };
+        for clause in cnf.into_iter() {
+            res.add(clause.iter().cloned().collect())
+        }
+        res
+    }
+
+    fn run(mut self) -> Preprocessed<Lit> {
+        self.simplify();
+        self.probing();
+        self.simplify();
+        self.elimination();
+        self.simplify();
+
+        let cnf = if self.unsat {
+            Cnf::new(vec![Clause::empty()])
+        } else {
+            Cnf::new(self.clauses.into_iter().flatten().collect())
+        };
+        Preprocessed {
+            cnf,
+            witnesses: self.witnesses,
+            stats: self.stats,
+        }
+    }
+
+    /// Number of clauses `lit` appears in.
+    fn occ_count(&self, lit: &Lit) -> usize {
+        self.occs.get(lit).map_or(0, BTreeSet::len)
+    }
+    /// Clauses `lit` appears in.
+    fn occurrences(&self, lit: &Lit) -> Vec<usize> {
+        self.occs
+            .get(lit)
+            .map(|occs| occs.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    /// Live clause at some index.
+    fn clause(&self, idx: usize) -> &Clause<Lit> {
+        self.clauses[idx]
+            .as_ref()
+            .expect("occurrence lists only mention live clauses")
+    }
+
+    /// Adds a clause, dropping duplicate literals and tautologies.
+    fn add(&mut self, mut lits: Vec<Lit>) {
+        lits.sort();
+        lits.dedup();
+        if lits
+            .iter()
+            .any(|lit| lits.binary_search(&lit.ref_negate()).is_ok())
+        {
+            self.stats.tautologies += 1;
+            return;
+        }
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => self.units.extend(lits),
+            _ => {
+                let idx = self.clauses.len();
+                for lit in &lits {
+                    let _is_new = self.occs.entry(lit.clone()).or_default().insert(idx);
+                    debug_assert!(_is_new)
+                }
+                self.clauses.push(Some(Clause::new(lits)));
+                self.touched.push(idx)
+            }
+        }
+    }
+
+    /// Removes a clause.
+    fn remove(&mut self, idx: usize) -> Clause<Lit> {
+        let clause = self.clauses[idx]
+            .take()
+            .expect("trying to remove a clause twice");
+        for lit in clause.iter() {
+            if let Some(occs) = self.occs.get_mut(lit) {
+                let _ = occs.remove(&idx);
+            }
+        }
+        clause
+    }
+
+    /// Removes a literal from a clause.
+    fn strengthen(&mut self, idx: usize, lit: &Lit) {
+        let clause = self.remove(idx);
+        self.add(clause.iter().filter(|l| *l != lit).cloned().collect());
+    }
+
+    /// Propagates the pending units.
+    ///
+    /// Fixed literals are removed from the formula, and remembered as unit clauses so that
+    /// reconstruction makes them true.
+    fn propagate(&mut self) {
+        while let Some(lit) = self.units.pop() {
+            if self.unsat || self.fixed.contains(&lit) {
+                continue;
+            } else if self.fixed.contains(&lit.ref_negate()) {
+                self.unsat = true;
+                continue;
+            }
+            log::trace!("fixing {}", lit);
+            let _ = self.fixed.insert(lit.clone());
+            self.stats.fixed += 1;
+            self.witnesses
+                .push((lit.clone(), Clause::new(vec![lit.clone()])));
+            for idx in self.occurrences(&lit) {
+                let _ = self.remove(idx);
+            }
+            let nlit = lit.ref_negate();
+            for idx in self.occurrences(&nlit) {
+                self.strengthen(idx, &nlit)
+            }
+        }
+    }
+
+    /// Removes the clauses subsumed by a clause, and strengthens the ones it subsumes after
+    /// resolution.
+    fn backward_subsume(&mut self, idx: usize) {
+        let clause = match &self.clauses[idx] {
+            Some(clause) => clause.clone(),
+            None => return,
+        };
+        // Clauses subsumed by `clause`, possibly after resolution, mention the variables of all its
+        // literals: only look at the occurrences of the least frequent one.
+        let pivot = clause
+            .iter()
+            .min_by_key(|lit| self.occ_count(lit) + self.occ_count(&lit.ref_negate()))
+            .expect("illegal empty clause in subsumption")
+            .clone();
+        let mut candidates = self.occurrences(&pivot);
+        candidates.extend(self.occurrences(&pivot.ref_negate()));
+
+        for other in candidates {
+            if other == idx {
+                continue;
+            }
+            let other_clause = match &self.clauses[other] {
+                Some(other_clause) if other_clause.len() >= clause.len() => other_clause,
+                _ => continue,
+            };
+            match subsumes(&clause, other_clause) {
+                None => (),
+                Some(None) => {
+                    if other_clause.len() == clause.len() {
+                        self.stats.duplicates += 1
+                    } else {
+                        self.stats.subsumed += 1
+                    }
+                    let _ = self.remove(other);
+                }
+                Some(Some(lit)) => {
+                    self.stats.strengthened += 1;
+                    self.strengthen(other, &lit)
+                }
+            }
+        }
+    }
+
+    /// Applies unit propagation and subsumption until fixpoint.
+    fn simplify(&mut self) {
+        loop {
+            self.propagate();
+            if self.unsat {
+                return;
+            }
+            match self.touched.pop() {
+                Some(idx) => self.backward_subsume(idx),
+                None => return,
+            }
+        }
+    }
+
+    /// True if assuming `lit` yields a conflict by unit propagation.
+    fn probe(&self, lit: &Lit) -> bool {
+        let mut assigned = Set::new();
+        let _ = assigned.insert(lit.clone());
+        let mut todo = vec![lit.clone()];
+        while let Some(lit) = todo.pop() {
+            'clauses: for idx in self.occs.get(&lit.ref_negate()).into_iter().flatten() {
+                let mut unassigned = vec![];
+                for lit in self.clause(*idx).iter() {
+                    if assigned.contains(lit) {
+                        // Clause is true.
+                        continue 'clauses;
+                    } else if !assigned.contains(&lit.ref_negate()) {
+                        unassigned.push(lit)
+                    }
+                }
+                match unassigned[..] {
+                    [] => return true,
+                    [lit] => {
+                        let _ = assigned.insert(lit.clone());
+                        todo.push(lit.clone())
+                    }
+                    _ => (),
+                }
+            }
+        }
+        false
+    }
+
+    /// Failed literal probing, fixes the negation of the literals whose propagation yields a
+    /// conflict.
+    ///
+    /// Only the variables of binary clauses are probed, since they are the ones that propagate.
+    fn probing(&mut self) {
+        let mut candidates: Vec<Lit> = self
+            .clauses
+            .iter()
+            .flatten()
+            .filter(|clause| clause.len() == 2)
+            .flat_map(|clause| clause.iter().map(|lit| lit.ref_negate()))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        candidates.truncate(Self::PROBE_LIMIT);
+
+        for lit in candidates {
+            if self.unsat {
+                return;
+            } else if self.fixed.contains(&lit) || self.fixed.contains(&lit.ref_negate()) {
+                continue;
+            }
+            if self.probe(&lit) {
+                log::trace!("failed literal {}", lit);
+                self.stats.failed += 1;
+                self.units.push(lit.ref_negate());
+                self.propagate()
+            }
+        }
+    }
+
+    /// Eliminates a variable if it does not increase the number of clauses, yields `true` if it
+    /// did.
+    fn eliminate(&mut self, var: &Lit) -> bool {
+        let nvar = var.ref_negate();
+        let (pos, neg) = (self.occurrences(var), self.occurrences(&nvar));
+        if pos.len() + neg.len() > Self::ELIM_OCC_LIMIT || pos.len() + neg.len() == 0 {
+            return false;
+        }
+        let mut resolvents = vec![];
+        for p in &pos {
+            for n in &neg {
+                if let Some(resolvent) = resolve(self.clause(*p), self.clause(*n), var) {
+                    if resolvent.len() > Self::ELIM_RESOLVENT_LIMIT
+                        || resolvents.len() >= pos.len() + neg.len()
+                    {
+                        return false;
+                    }
+                    resolvents.push(resolvent)
+                }
+            }
+        }
+
+        log::trace!("eliminating {}", var);
+        self.stats.eliminated += 1;
+        for idx in pos {
+            let clause = self.remove(idx);
+            self.witnesses.push((var.clone(), clause))
+        }
+        for idx in neg {
+            let clause = self.remove(idx);
+            self.witnesses.push((nvar.clone(), clause))
+        }
+        for resolvent in resolvents {
+            self.add(resolvent)
+        }
+        true
+    }
+
+    /// Bounded variable elimination, variables with few occurrences first.
+    fn elimination(&mut self) {
+        // Variables are represented by their smallest literal.
+        let mut vars: Vec<Lit> = self
+            .occs
+            .iter()
+            .filter(|(_, occs)| !occs.is_empty())
+            .map(|(lit, _)| lit.clone().min(lit.ref_negate()))
+            .collect();
+        vars.sort();
+        vars.dedup();
+        let mut vars: Vec<(usize, Lit)> = vars
+            .into_iter()
+            .map(|var| {
+                (
+                    self.occ_count(&var) + self.occ_count(&var.ref_negate()),
+                    var,
+                )
+            })
+            .collect();
+        vars.sort();
+
+        for (_, var) in vars {
+            if self.unsat {
+                return;
+            }
+            if self.eliminate(&var) {
+                self.propagate()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expr, rng::Rng};
+
+    type Lit = expr::Lit<usize>;
+
+    fn lit(int: i64) -> Lit {
+        let lit = Lit::named(int.unsigned_abs() as usize);
+        if int < 0 {
+            lit.negate()
+        } else {
+            lit
+        }
+    }
+
+    fn cnf_of(clauses: &[&[i64]]) -> Cnf<Lit> {
+        Cnf::new(
+            clauses
+                .iter()
+                .map(|lits| Clause::new(lits.iter().cloned().map(lit).collect()))
+                .collect(),
+        )
+    }
+
+    /// Total assignments of the variables `1..=var_count`.
+    fn assignments(var_count: usize) -> impl Iterator<Item = Set<Lit>> {
+        (0..1u64 << var_count).map(move |bits| {
+            (1..=var_count)
+                .map(|var| {
+                    let val = bits >> (var - 1) & 1 == 1;
+                    lit(if val { var as i64 } else { -(var as i64) })
+                })
+                .collect()
+        })
+    }
+
+    /// Checks that `reconstruct` turns all the models of the preprocessed CNF into models of
+    /// `cnf`, and that preprocessing preserves satisfiability.
+    ///
+    /// Models of the preprocessed CNF only mention its variables, as the ones of the solvers.
+    fn check_reconstruct(cnf: &Cnf<Lit>, var_count: usize) -> Preprocessed<Lit> {
+        let pre = preprocess(cnf.clone());
+        let vars: Set<Lit> = pre
+            .cnf()
+            .iter()
+            .flat_map(|clause| clause.iter())
+            .map(|lit| lit.clone().min(lit.ref_negate()))
+            .collect();
+        let mut sat = false;
+        for assignment in assignments(var_count) {
+            let model: Set<Lit> = assignment
+                .into_iter()
+                .filter(|lit| vars.contains(&lit.clone().min(lit.ref_negate())))
+                .collect();
+            if pre.cnf().first_falsified(&model).is_some() {
+                continue;
+            }
+            sat = true;
+            let model = pre.reconstruct(model);
+            assert!(
+                model.iter().all(|lit| !model.contains(&lit.ref_negate())),
+                "inconsistent reconstructed model"
+            );
+            assert_eq!(
+                cnf.first_falsified(&model),
+                None,
+                "reconstructed model falsifies a clause"
+            );
+        }
+        let original_sat =
+            assignments(var_count).any(|model| cnf.first_falsified(&model).is_none());
+        assert_eq!(sat, original_sat, "preprocessing changed satisfiability");
+        pre
+    }
+
+    #[test]
+    fn eliminated() {
+        let cnf = cnf_of(&[
+            &[1, 2],
+            &[-1, 3],
+            &[2, 3, 4],
+            &[-2, -3, -4],
+            &[-4, 5],
+            &[4, -5],
+        ]);
+        let pre = check_reconstruct(&cnf, 5);
+        assert!(pre.stats().eliminated > 0);
+    }
+
+    #[test]
+    fn fixed_and_eliminated() {
+        let cnf = cnf_of(&[
+            &[1],
+            &[-1, 2, 3],
+            &[-2, 4],
+            &[-3, 4],
+            &[-4, 5, 6],
+            &[-5, -6],
+        ]);
+        let pre = check_reconstruct(&cnf, 6);
+        assert!(pre.stats().fixed > 0);
+        assert!(pre.stats().eliminated > 0);
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Rng::new(0);
+        let var_count = 8;
+        let mut vars: Vec<i64> = (1..=var_count as i64).collect();
+        let (mut eliminated, mut fixed) = (0, 0);
+        for _ in 0..1000 {
+            let clause_count = 8 + rng.below(20);
+            let clauses: Vec<Vec<i64>> = (0..clause_count)
+                .map(|_| {
+                    let len = 1 + rng.below(3);
+                    rng.partial_shuffle(&mut vars, len);
+                    vars[..len]
+                        .iter()
+                        .map(|var| if rng.bool() { *var } else { -var })
+                        .collect()
+                })
+                .collect();
+            let clauses: Vec<&[i64]> = clauses.iter().map(Vec::as_slice).collect();
+            let pre = check_reconstruct(&cnf_of(&clauses), var_count);
+            eliminated += pre.stats().eliminated;
+            fixed += pre.stats().fixed;
+        }
+        assert!(eliminated > 0 && fixed > 0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Proofs of unsatisfiability.
+//!
+//! Solvers that support proof production log the clauses they learn to a [`Proof`], ending with the
+//! empty clause when the formula is unsat. Every clause logged is implied by the formula and the
+//! clauses logged before it by unit propagation, which makes the result a valid DRAT proof.
+
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// A sink for clausal proofs.
+pub trait Proof<Lit> {
+    /// Logs a lemma, *i.e.* a clause implied by the formula and the previous lemmas.
+    fn add(&mut self, lits: &[Lit]);
+    /// Logs the deletion of a clause.
+    fn delete(&mut self, lits: &[Lit]);
+}
+
+impl<Lit, P: Proof<Lit> + ?Sized> Proof<Lit> for &mut P {
+    fn add(&mut self, lits: &[Lit]) {
+        (**self).add(lits)
+    }
+    fn delete(&mut self, lits: &[Lit]) {
+        (**self).delete(lits)
+    }
+}
+/// Logs to both proofs.
+impl<Lit, P1: Proof<Lit>, P2: Proof<Lit>> Proof<Lit> for (P1, P2) {
+    fn add(&mut self, lits: &[Lit]) {
+        self.0.add(lits);
+        self.1.add(lits)
+    }
+    fn delete(&mut self, lits: &[Lit]) {
+        self.0.delete(lits);
+        self.1.delete(lits)
+    }
+}
+
+/// Writes a proof in the textual DRAT format.
+///
+/// Literals are written using their [`Display`] implementation, which must produce DIMACS
+/// literals (non-zero integers) for the proof to be valid.
+pub struct Drat<W: Write> {
+    writer: W,
+    /// First error that happened while writing, if any.
+    error: Option<io::Error>,
+}
+impl<W: Write> Drat<W> {
+    /// Constructor.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flushes the underlying writer and returns it.
+    ///
+    /// Fails with the first error that happened while writing, if any.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write<Lit: Display>(&mut self, pref: &str, lits: &[Lit]) {
+        if self.error.is_some() {
+            return;
+        }
+        let res = (|| {
+            self.writer.write_all(pref.as_bytes())?;
+            for lit in lits {
+                write!(self.writer, "{} ", lit)?
+            }
+            writeln!(self.writer, "0")
+        })();
+        if let Err(e) = res {
+            self.error = Some(e)
+        }
+    }
+}
+impl<Lit: Display, W: Write> Proof<Lit> for Drat<W> {
+    fn add(&mut self, lits: &[Lit]) {
+        self.write("", lits)
+    }
+    fn delete(&mut self, lits: &[Lit]) {
+        self.write("d ", lits)
+    }
+}
+
+/// Clause stating that the literals in `deps` imply `lit`, *i.e.* `lit ∨ ¬deps`.
+pub fn implication<Lit: Literal>(lit: &Lit, deps: &Set<Lit>) -> Vec<Lit> {
+    let mut clause = Vec::with_capacity(deps.len() + 1);
+    clause.push(lit.clone());
+    clause.extend(deps.iter().map(Literal::ref_negate));
+    clause
+}
@@ -1,20 +1,63 @@
 //! Functional versions taken directly from the paper.
 
-use crate::prelude::*;
+use crate::{prelude::*, proof::Proof, stats::Stats};
 
 mod backjump;
 mod cdcl;
 mod plain;
 
-pub use self::{backjump::Backjump, cdcl::Cdcl, plain::Plain};
+pub use self::{
+    backjump::Backjump,
+    cdcl::{Cdcl, Search},
+    plain::Plain,
+};
 
-pub fn solve<F>(f: F, dpll: Dpll) -> Result<Outcome<F::Lit, ()>, String>
+/// Solves a formula, logging a proof of unsatisfiability to `proof` (if any).
+///
+/// `options` gives the branching heuristic making the decisions, (de)activates the *pure* rule and
+/// gives the restart policy and learned clause database settings of [`Dpll::Cdcl`]. Yields
+/// [`Outcome::Unknown`] if `cancel` is cancelled before the search ends.
+///
+/// Fails if `proof` is not `None` and `dpll` is [`Dpll::Plain`], which does not support proofs, or
+/// the *pure* rule is active.
+pub fn solve<F>(
+    f: F,
+    dpll: Dpll,
+    options: &Options,
+    proof: Option<&mut (dyn Proof<F::Lit> + '_)>,
+    cancel: &Cancel,
+) -> Result<(Outcome<F::Lit, ()>, Stats), String>
 where
     F: Formula,
 {
+    if let (Dpll::Plain, Some(_)) = (dpll, proof.as_ref()) {
+        return Err(format!("{} does not support proofs", dpll));
+    }
+    if options.pure && proof.is_some() {
+        return Err("the pure rule does not support proofs".into());
+    }
+    let cnf = f.into_cnf();
+    // The *unsat* rules do not support empty clauses in the input.
+    if cnf.iter().any(|clause| clause.is_empty()) {
+        if let Some(proof) = proof {
+            proof.add(&[])
+        }
+        return Ok((Outcome::Unsat(()), Stats::new()));
+    }
+    let mut branch = options.branching.build();
     match dpll {
-        Dpll::Plain => Ok(Plain::new(f).solve()),
-        Dpll::Backjump => Ok(Backjump::new(f).solve()),
-        Dpll::Cdcl => Ok(Cdcl::new(f).solve()),
+        Dpll::Plain => Ok(Plain::new(cnf)
+            .with_pure(options.pure)
+            .solve(&mut *branch, cancel)),
+        Dpll::Backjump => Ok(Backjump::new(cnf).with_pure(options.pure).solve_with_proof(
+            proof,
+            &mut *branch,
+            cancel,
+        )),
+        Dpll::Cdcl => Ok(Cdcl::new(cnf)
+            .with_pure(options.pure)
+            .with_restarts(options.restarts)
+            .with_reduction(options.reduction)
+            .solve_with_proof(proof, &mut *branch, cancel)),
     }
 }
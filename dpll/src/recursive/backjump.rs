@@ -1,6 +1,6 @@
 //! Augments the [`Plain` solver][super::Plain] with backjumping.
 
-use crate::prelude::*;
+use crate::{branch::Branch, prelude::*, proof::Proof, stats::Stats};
 
 /// Alias for a map from `Lit`s to sets of `Lit`s.
 pub type Γ<Lit> = Map<Lit, Set<Lit>>;
@@ -8,6 +8,7 @@ pub type Γ<Lit> = Map<Lit, Set<Lit>>;
 macro_rules! raise {
 	{ sat $γ:expr } => { return Err(Outcome::Sat($γ)) };
 	{ unsat $deps:expr } => { return Err(Outcome::Unsat($deps)) };
+	{ unknown } => { return Err(Outcome::Unknown) };
 }
 
 pub type Out<Lit> = Outcome<Lit, Set<Lit>>;
@@ -20,6 +21,8 @@ pub struct Backjump<Lit: Literal> {
     γ: Γ<Lit>,
     /// CNF we're working on.
     δ: LCnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
 }
 
 implem! {
@@ -42,8 +45,15 @@ impl<Lit: Literal> Backjump<Lit> {
         Self {
             γ: Γ::new(),
             δ: f.into_cnf().into(),
+            pure: false,
         }
     }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
 }
 
 impl<Lit: Literal> Backjump<Lit> {
@@ -68,7 +78,7 @@ impl<Lit: Literal> Backjump<Lit> {
     }
 
     /// *Assume* rule.
-    pub fn assume(&self, lit: Lit, cause: Set<Lit>) -> Res<Self, Lit> {
+    pub fn assume(&self, lit: Lit, cause: Set<Lit>, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("assume({})", lit);
         self.invariant();
         let mut new: Self = self.clone();
@@ -81,18 +91,19 @@ impl<Lit: Literal> Backjump<Lit> {
             }
             Vacant(entry) => {
                 entry.insert(cause);
-                new.bcp()
+                new.bcp(stats)
             }
         }
     }
 
     /// *BCP* rule.
-    pub fn bcp(&self) -> Res<Self, Lit> {
+    pub fn bcp(&self, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("bcp(), γ.len(): {}", self.γ.len());
         self.invariant();
         let mut new = Self {
             γ: self.γ.clone(),
             δ: LCnf::with_capacity(self.δ.len()),
+            pure: self.pure,
         };
         let mut new_clause = Clause::with_capacity(5);
         let mut new_deps = Set::with_capacity(11);
@@ -144,13 +155,15 @@ impl<Lit: Literal> Backjump<Lit> {
             new_deps.extend(lclause.labels.iter().cloned());
 
             if new_clause.is_empty() {
+                stats.conflicts += 1;
                 raise!(unsat new_deps)
             } else {
                 if new_clause.len() == 1 {
+                    stats.propagations += 1;
                     let lit = new_clause.drain(0..).next().expect("unreachable");
                     let mut deps = Set::with_capacity(new_deps.len());
                     deps.extend(new_deps.drain());
-                    new = new.assume(lit, deps)?;
+                    new = new.assume(lit, deps, stats)?;
                 } else {
                     new.δ.push(LClause::new_with(
                         new_clause.drain(0..).collect(),
@@ -163,53 +176,130 @@ impl<Lit: Literal> Backjump<Lit> {
         Ok(new)
     }
 
-    pub fn unsat(&self) -> Res<Empty, Lit> {
+    /// *Pure* rule, assumes the literals whose negation does not appear in `δ`.
+    ///
+    /// Yields `None` if the rule is inactive or there are no pure literals. A pure literal is not
+    /// implied by anything, but if the formula is unsat under the current decisions and a pure
+    /// literal, then it is unsat under the current decisions alone: pure literals depend on all of
+    /// them. Assuming pure literals only drops clauses, which can make other literals pure: the rule
+    /// is applied until there are none left.
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter().map(|lclause| lclause.clause()));
+        if pure.is_empty() {
+            return None;
+        }
+        // Dependencies only mention decisions, and decisions depend on themselves.
+        let deps: Set<Lit> = self.γ.values().flatten().cloned().collect();
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|lclause| !lclause.iter().any(|lit| pure.contains(lit)));
+            for lit in pure {
+                let _prev = new.γ.insert(lit, deps.clone());
+                debug_assert!(_prev.is_none())
+            }
+            pure = crate::pure_literals(new.δ.iter().map(|lclause| lclause.clause()));
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule.
+    ///
+    /// When a conflict makes us explore the second branch of a decision, logs the lemma
+    /// justifying it to `proof` (if any). `depth` is the number of decisions leading to `self`.
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding. Raises an unknown result
+    /// if `cancel` is cancelled before a decision, which is made by `branch`.
+    pub fn unsat(
+        &self,
+        depth: usize,
+        stats: &mut Stats,
+        cancel: &Cancel,
+        branch: &mut (dyn Branch<Lit> + '_),
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+    ) -> Res<Empty, Lit> {
         log::debug!("unsat()");
         self.invariant();
         if self.δ.is_empty() {
             raise!(sat self.γ.iter().map(|(lit, _)| lit.clone()).collect())
+        } else if cancel.is_cancelled() {
+            raise!(unknown)
+        } else if let Some(new) = self.pure(stats) {
+            new.unsat(depth, stats, cancel, branch, proof)
         } else {
-            let disj = &self.δ[0];
-            if let Some(lit) = disj.iter().next() {
-                let mut deps = Set::new();
-                let _is_new = deps.insert(lit.clone());
-                debug_assert!(_is_new);
-
-                let mut deps = match self.assume(lit.clone(), deps).and_then(|new| new.unsat()) {
-                    // Unreachable.
-                    Ok(empty) => match empty {},
-                    // Sat, propagate sat result.
-                    Err(sat_res @ Out::Sat(_)) => return Err(sat_res),
-                    // Conflict, move on.
-                    Err(Out::Unsat(deps)) => deps,
-                };
-
-                log::debug!(
-                    "handling unsat branch with deps:{}",
-                    deps.iter().fold(String::new(), |mut acc, lit| {
-                        acc.push_str(" ");
-                        acc.push_str(&lit.to_string());
-                        acc
-                    })
-                );
+            let lit = branch.pick(&mut self.δ.iter().map(|lclause| lclause.clause()));
+            let mut deps = Set::new();
+            let _is_new = deps.insert(lit.clone());
+            debug_assert!(_is_new);
+            stats.decision(depth + 1);
 
-                let lit_was_there = deps.remove(lit);
-                if !lit_was_there {
-                    raise!(unsat deps)
-                } else {
-                    let n_lit = lit.ref_negate();
-                    self.assume(n_lit, deps)?.unsat()
-                }
+            let mut deps = match self
+                .assume(lit.clone(), deps, stats)
+                .and_then(|new| new.unsat(depth + 1, stats, cancel, branch, proof.as_deref_mut()))
+            {
+                // Unreachable.
+                Ok(empty) => match empty {},
+                // Sat or cancelled, propagate result.
+                Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                // Conflict, move on.
+                Err(Out::Unsat(deps)) => deps,
+            };
+            branch.conflict(&deps.iter().map(Literal::ref_negate).collect::<Vec<_>>());
+
+            log::debug!(
+                "handling unsat branch with deps:{}",
+                deps.iter().fold(String::new(), |mut acc, lit| {
+                    acc.push_str(" ");
+                    acc.push_str(&lit.to_string());
+                    acc
+                })
+            );
+
+            let lit_was_there = deps.remove(&lit);
+            if !lit_was_there {
+                stats.backjumped += 1;
+                raise!(unsat deps)
             } else {
-                panic!("illegal empty disjunct in application of `unsat` rule")
+                let n_lit = lit.ref_negate();
+                if let Some(proof) = proof.as_deref_mut() {
+                    proof.add(&crate::proof::implication(&n_lit, &deps))
+                }
+                self.assume(n_lit, deps, stats)?
+                    .unsat(depth + 1, stats, cancel, branch, proof)
             }
         }
     }
 
-    pub fn solve(&self) -> Outcome<Lit, ()> {
-        match self.unsat() {
-            Err(res) => res.into_unit_unsat(),
+    pub fn solve(
+        &self,
+        branch: &mut (dyn Branch<Lit> + '_),
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        self.solve_with_proof(None, branch, cancel)
+    }
+
+    /// Same as [`Self::solve`], but logs a proof of unsatisfiability to `proof` (if any).
+    pub fn solve_with_proof(
+        &self,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+        branch: &mut (dyn Branch<Lit> + '_),
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        let mut stats = Stats::new();
+        let res = match self.unsat(0, &mut stats, cancel, branch, proof.as_deref_mut()) {
+            Err(res) => {
+                if let (Some(proof), true) = (proof, res.is_unsat()) {
+                    proof.add(&[])
+                }
+                res.into_unit_unsat()
+            }
             Ok(empty) => match empty {},
-        }
+        };
+        (res, stats)
     }
 }
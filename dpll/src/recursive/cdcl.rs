@@ -1,20 +1,68 @@
 //! Augments the [`Backjump` solver][super::Backjump] with CDCL.
 
-use crate::prelude::*;
+use crate::{
+    branch::Branch, learned::Reducer, prelude::*, proof::Proof, restart::Policy, stats::Stats,
+};
 
 /// Alias for a map from `Lit`s to sets of `Lit`s.
 pub type Γ<Lit> = Map<Lit, Set<Lit>>;
 
 macro_rules! raise {
-	{ sat $γ:expr } => { return Err(Outcome::Sat($γ)) };
-	{ unsat $deps:expr } => { return Err(Outcome::Unsat($deps)) };
+	{ sat $γ:expr } => { return Err(Out::Sat($γ)) };
+	{ unsat $deps:expr } => { return Err(Out::Unsat($deps)) };
+	{ unknown } => { return Err(Out::Unknown) };
+	{ restart $learned:expr } => { return Err(Out::Restart($learned)) };
 }
 
 pub type LClauses<Lit> = Set<LClause<Lit>>;
 
-pub type Out<Lit> = Outcome<Lit, (Set<Lit>, LClauses<Lit>)>;
+/// Results raised by the rules, an [`Outcome`] that can also be a restart.
+#[derive(Debug, Clone)]
+pub enum Out<Lit: Literal> {
+    /// Sat result, with a model.
+    Sat(Set<Lit>),
+    /// Conflict, with its dependencies and the conflict clauses learned so far.
+    Unsat((Set<Lit>, LClauses<Lit>)),
+    /// The search was cancelled.
+    Unknown,
+    /// The restart policy triggered a restart, with the conflict clauses learned so far.
+    Restart(LClauses<Lit>),
+}
 pub type Res<T, Lit> = Result<T, Out<Lit>>;
 
+/// State of a search shared by all its decisions, see [`Cdcl::unsat`].
+///
+/// Also used by the [iterative version][crate::iterative::Cdcl].
+pub struct Search<'a, Lit: Literal> {
+    /// Statistics of the search so far.
+    pub stats: Stats,
+    /// The search stops before a decision once this is cancelled.
+    pub cancel: &'a Cancel,
+    /// Branching heuristic making the decisions.
+    pub branch: &'a mut (dyn Branch<Lit> + 'a),
+    /// Restart policy, registers each conflict leading to a second branch.
+    pub restart: Policy,
+    /// Decides when to delete learned clauses, see [`Cdcl::reduce`].
+    pub reducer: Reducer,
+}
+impl<'a, Lit: Literal> Search<'a, Lit> {
+    /// Constructor, with empty statistics.
+    pub fn new(
+        cancel: &'a Cancel,
+        branch: &'a mut (dyn Branch<Lit> + 'a),
+        restarts: Restarts,
+        reduction: Reduction,
+    ) -> Self {
+        Self {
+            stats: Stats::new(),
+            cancel,
+            branch,
+            restart: restarts.build(),
+            reducer: reduction.build(),
+        }
+    }
+}
+
 /// Backjump + CDCL solver.
 #[derive(Clone)]
 pub struct Cdcl<Lit: Literal> {
@@ -22,6 +70,12 @@ pub struct Cdcl<Lit: Literal> {
     γ: Γ<Lit>,
     /// CNF we're working on.
     δ: LCnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
+    /// Restart policy.
+    restarts: Restarts,
+    /// Learned clause database settings.
+    reduction: Reduction,
 }
 
 implem! {
@@ -44,8 +98,29 @@ impl<Lit: Literal> Cdcl<Lit> {
         Self {
             γ: Γ::new(),
             δ: f.into_cnf().into(),
+            pure: false,
+            restarts: Restarts::Never,
+            reduction: Reduction::new(),
         }
     }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+
+    /// Sets the restart policy, [`Restarts::Never`] by default.
+    pub fn with_restarts(mut self, restarts: Restarts) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Sets the learned clause database settings, learned clauses are never deleted by default.
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
 }
 
 impl<Lit: Literal> Cdcl<Lit> {
@@ -86,8 +161,41 @@ impl<Lit: Literal> Cdcl<Lit> {
         res
     }
 
+    /// Reduces some conflict clauses if `reducer` says there are too many of them.
+    ///
+    /// The LBD of a conflict clause is its length plus its number of labels, since its literals and
+    /// labels all stem from distinct decisions. These solvers do not track the activity of clauses,
+    /// ties are broken by the order of the clauses. Deletions are not logged to proofs, which stay
+    /// valid without them.
+    pub fn reduce(
+        reducer: &mut Reducer,
+        learned: LClauses<Lit>,
+        stats: &mut Stats,
+    ) -> LClauses<Lit> {
+        if !reducer.should_reduce(learned.len()) {
+            return learned;
+        }
+        let mut learned: Vec<_> = learned.into_iter().collect();
+        // Sets have no deterministic order.
+        learned.sort_by_cached_key(|lclause| {
+            let mut labels: Vec<_> = lclause.labels().iter().cloned().collect();
+            labels.sort();
+            (lclause.clause().clone(), labels)
+        });
+        let learned = learned
+            .into_iter()
+            .map(|lclause| {
+                let lbd = lclause.len() + lclause.labels().len();
+                (lclause, lbd, 0.0)
+            })
+            .collect();
+        let (kept, deleted) = reducer.reduce(learned);
+        stats.deleted += deleted.len();
+        kept.into_iter().collect()
+    }
+
     /// *Assume* rule.
-    pub fn assume(&self, lit: Lit, cause: Set<Lit>) -> Res<Self, Lit> {
+    pub fn assume(&self, lit: Lit, cause: Set<Lit>, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("assume({})", lit);
         self.invariant();
         let mut new: Self = self.clone();
@@ -100,18 +208,21 @@ impl<Lit: Literal> Cdcl<Lit> {
             }
             Vacant(entry) => {
                 entry.insert(cause);
-                new.bcp()
+                new.bcp(stats)
             }
         }
     }
 
     /// *BCP* rule.
-    pub fn bcp(&self) -> Res<Self, Lit> {
+    pub fn bcp(&self, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("bcp(), γ.len(): {}", self.γ.len());
         self.invariant();
         let mut new = Self {
             γ: self.γ.clone(),
             δ: LCnf::with_capacity(self.δ.len()),
+            pure: self.pure,
+            restarts: self.restarts,
+            reduction: self.reduction,
         };
         let mut new_clause = Clause::with_capacity(5);
         let mut new_deps = Set::with_capacity(5);
@@ -163,13 +274,15 @@ impl<Lit: Literal> Cdcl<Lit> {
             new_deps.extend(lclause.labels.iter().cloned());
 
             if new_clause.is_empty() {
+                stats.conflicts += 1;
                 raise!(unsat(new_deps, LClauses::new()))
             } else {
                 if new_clause.len() == 1 {
+                    stats.propagations += 1;
                     let lit = new_clause.drain(0..).next().expect("unreachable");
                     let mut deps = Set::with_capacity(new_deps.len());
                     deps.extend(new_deps.drain());
-                    new = new.assume(lit, deps)?;
+                    new = new.assume(lit, deps, stats)?;
                 } else {
                     new.δ.push(LClause::new_with(
                         new_clause.drain(0..).collect(),
@@ -182,74 +295,207 @@ impl<Lit: Literal> Cdcl<Lit> {
         Ok(new)
     }
 
-    pub fn unsat(&self) -> Res<Empty, Lit> {
+    /// *Pure* rule, see the [backjump version][super::Backjump::pure].
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter().map(|lclause| lclause.clause()));
+        if pure.is_empty() {
+            return None;
+        }
+        // Dependencies only mention decisions, and decisions depend on themselves.
+        let deps: Set<Lit> = self.γ.values().flatten().cloned().collect();
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|lclause| !lclause.iter().any(|lit| pure.contains(lit)));
+            for lit in pure {
+                let _prev = new.γ.insert(lit, deps.clone());
+                debug_assert!(_prev.is_none())
+            }
+            pure = crate::pure_literals(new.δ.iter().map(|lclause| lclause.clause()));
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule.
+    ///
+    /// When a conflict makes us explore the second branch of a decision, logs the lemma
+    /// justifying it to `proof` (if any). `depth` is the number of decisions leading to `self`.
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding. Raises an unknown result
+    /// if `search.cancel` is cancelled before a decision, which is made by `search.branch`.
+    ///
+    /// Each conflict leading to a second branch is registered to `search.restart`. When it triggers
+    /// a restart, raises all the conflict clauses learned so far instead, shifted so that they only
+    /// depend on the decisions above `self`. Raised conflict clauses are [reduced][Self::reduce] by
+    /// `search.reducer`.
+    pub fn unsat(
+        &self,
+        depth: usize,
+        search: &mut Search<'_, Lit>,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+    ) -> Res<Empty, Lit> {
         log::debug!("unsat()");
         self.invariant();
         if self.δ.is_empty() {
             raise!(sat self.γ.iter().map(|(lit, _)| lit.clone()).collect())
+        } else if search.cancel.is_cancelled() {
+            raise!(unknown)
+        } else if let Some(new) = self.pure(&mut search.stats) {
+            new.unsat(depth, search, proof)
         } else {
-            let disj = &self.δ[0];
-            if let Some(lit) = disj.iter().next() {
-                let mut deps = Set::new();
-                let _is_new = deps.insert(lit.clone());
-                debug_assert!(_is_new);
-
-                let (mut deps, mut conflict) =
-                    match self.assume(lit.clone(), deps).and_then(|new| new.unsat()) {
-                        // Unreachable.
-                        Ok(empty) => match empty {},
-                        // Sat, propagate sat result.
-                        Err(sat_res @ Out::Sat(_)) => return Err(sat_res),
-                        // Conflict, move on.
-                        Err(Out::Unsat(deps)) => deps,
-                    };
-
-                conflict = Self::shift(lit, &conflict);
-
-                log::debug!(
-                    "handling unsat branch with deps:{}",
-                    deps.iter().fold(String::new(), |mut acc, lit| {
-                        acc.push_str(" ");
-                        acc.push_str(&lit.to_string());
-                        acc
-                    })
-                );
+            let lit = search
+                .branch
+                .pick(&mut self.δ.iter().map(|lclause| lclause.clause()));
+            let mut deps = Set::new();
+            let _is_new = deps.insert(lit.clone());
+            debug_assert!(_is_new);
+            search.stats.decision(depth + 1);
 
-                let lit_was_there = deps.remove(lit);
-                if !lit_was_there {
-                    raise!(unsat(deps, conflict))
-                } else {
-                    let nlit = lit.ref_negate();
-                    match {
-                        if conflict.is_empty() {
-                            self.assume(nlit, deps.clone())?.unsat()
-                        } else {
-                            let mut new = self.clone();
-                            new.δ.extend(conflict.iter().cloned());
-                            new.assume(nlit, deps.clone())?.unsat()
-                        }
-                    } {
-                        Ok(empty) => match empty {},
-                        Err(sat_res @ Out::Sat(_)) => return Err(sat_res),
-                        Err(Out::Unsat((new_deps, new_conflict))) => {
-                            conflict.extend(new_conflict);
-                            let conflict_clause =
-                                LClause::new_with(Clause::new(vec![lit.ref_negate()]), deps);
-                            conflict.insert(conflict_clause);
-                            raise!(unsat(new_deps, conflict))
-                        }
+            let (mut deps, mut conflict) = match self
+                .assume(lit.clone(), deps, &mut search.stats)
+                .and_then(|new| new.unsat(depth + 1, search, proof.as_deref_mut()))
+            {
+                // Unreachable.
+                Ok(empty) => match empty {},
+                // Sat or cancelled, propagate result.
+                Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                // Restart, the learned clauses might depend on `lit`.
+                Err(Out::Restart(learned)) => raise!(restart Self::shift(&lit, &learned)),
+                // Conflict, move on.
+                Err(Out::Unsat(deps)) => deps,
+            };
+            search
+                .branch
+                .conflict(&deps.iter().map(Literal::ref_negate).collect::<Vec<_>>());
+
+            conflict = Self::shift(&lit, &conflict);
+
+            log::debug!(
+                "handling unsat branch with deps:{}",
+                deps.iter().fold(String::new(), |mut acc, lit| {
+                    acc.push_str(" ");
+                    acc.push_str(&lit.to_string());
+                    acc
+                })
+            );
+
+            let lit_was_there = deps.remove(&lit);
+            if !lit_was_there {
+                search.stats.backjumped += 1;
+                raise!(unsat(deps, conflict))
+            } else {
+                let nlit = lit.ref_negate();
+                if let Some(proof) = proof.as_deref_mut() {
+                    proof.add(&crate::proof::implication(&nlit, &deps))
+                }
+                // The lemma above is asserting, its literals are the negations of `lit` and of the
+                // decisions in `deps`, which all have their own decision level.
+                if search.restart.conflict(deps.len() + 1) {
+                    log::debug!("restarting");
+                    conflict.insert(LClause::new_with(Clause::new(vec![nlit]), deps));
+                    search.stats.learned += 1;
+                    raise!(restart conflict)
+                }
+                match {
+                    if conflict.is_empty() {
+                        self.assume(nlit, deps.clone(), &mut search.stats)?.unsat(
+                            depth + 1,
+                            search,
+                            proof,
+                        )
+                    } else {
+                        let mut new = self.clone();
+                        new.δ.extend(conflict.iter().cloned());
+                        new.assume(nlit, deps.clone(), &mut search.stats)?.unsat(
+                            depth + 1,
+                            search,
+                            proof,
+                        )
+                    }
+                } {
+                    Ok(empty) => match empty {},
+                    Err(res @ Out::Sat(_)) | Err(res @ Out::Unknown) => return Err(res),
+                    // Restart, `nlit` is not a decision so the learned clauses do not depend on
+                    // it.
+                    Err(Out::Restart(mut learned)) => {
+                        learned.extend(conflict);
+                        let conflict_clause =
+                            LClause::new_with(Clause::new(vec![lit.ref_negate()]), deps);
+                        learned.insert(conflict_clause);
+                        search.stats.learned += 1;
+                        raise!(restart learned)
+                    }
+                    Err(Out::Unsat((new_deps, new_conflict))) => {
+                        conflict.extend(new_conflict);
+                        let conflict_clause =
+                            LClause::new_with(Clause::new(vec![lit.ref_negate()]), deps);
+                        conflict.insert(conflict_clause);
+                        search.stats.learned += 1;
+                        let conflict =
+                            Self::reduce(&mut search.reducer, conflict, &mut search.stats);
+                        raise!(unsat(new_deps, conflict))
                     }
                 }
-            } else {
-                panic!("illegal empty disjunct in application of `unsat` rule")
             }
         }
     }
 
-    pub fn solve(&self) -> Outcome<Lit, ()> {
-        match self.unsat() {
-            Err(res) => res.into_unit_unsat(),
-            Ok(empty) => match empty {},
-        }
+    pub fn solve(
+        &self,
+        branch: &mut (dyn Branch<Lit> + '_),
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        self.solve_with_proof(None, branch, cancel)
+    }
+
+    /// Same as [`Self::solve`], but logs a proof of unsatisfiability to `proof` (if any).
+    ///
+    /// On restarts, the search starts over from `self` augmented with the clauses learned so far,
+    /// which are [reduced][Self::reduce] and propagated first. Restarts stop if one of them does
+    /// not learn any new clause.
+    pub fn solve_with_proof(
+        &self,
+        mut proof: Option<&mut (dyn Proof<Lit> + '_)>,
+        branch: &mut (dyn Branch<Lit> + '_),
+        cancel: &Cancel,
+    ) -> (Outcome<Lit, ()>, Stats) {
+        let mut search = Search::new(cancel, branch, self.restarts, self.reduction);
+        let mut learned = LClauses::new();
+        let mut root = Ok(self.clone());
+        let res = loop {
+            let res = root.and_then(|root| root.unsat(0, &mut search, proof.as_deref_mut()));
+            match res {
+                Err(Out::Sat(model)) => break Outcome::Sat(model),
+                Err(Out::Unsat(_)) => {
+                    if let Some(proof) = proof.as_deref_mut() {
+                        proof.add(&[])
+                    }
+                    break Outcome::Unsat(());
+                }
+                Err(Out::Unknown) => break Outcome::Unknown,
+                Err(Out::Restart(new_learned)) => {
+                    search.stats.restarts += 1;
+                    let count = learned.len();
+                    learned.extend(new_learned);
+                    if learned.len() == count {
+                        // Nothing new to start over with, restarting again could loop forever.
+                        search.restart = Restarts::Never.build()
+                    }
+                    learned = Self::reduce(&mut search.reducer, learned, &mut search.stats);
+                    // Propagate the learned clauses before deciding, otherwise the search could
+                    // make the same decisions again and reach the same conflicts.
+                    let mut new = self.clone();
+                    new.δ.extend(learned.iter().cloned());
+                    root = new.bcp(&mut search.stats)
+                }
+                Ok(empty) => match empty {},
+            }
+        };
+        (res, search.stats)
     }
 }
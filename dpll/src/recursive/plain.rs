@@ -1,6 +1,6 @@
 //! Plain DPLL version, with no optimizations.
 
-use crate::prelude::*;
+use crate::{branch::Branch, prelude::*, stats::Stats};
 
 /// Alias for a set of `Lit`.
 pub type Γ<Lit> = Set<Lit>;
@@ -10,6 +10,7 @@ pub type Out<Lit> = crate::Outcome<Lit, ()>;
 macro_rules! raise {
 	{ sat $γ:expr } => { return Err(Out::Sat($γ)) };
 	{ unsat } => { return Err(Out::Unsat(())) };
+	{ unknown } => { return Err(Out::Unknown) };
 }
 
 pub type Res<T, Lit> = Result<T, Out<Lit>>;
@@ -21,6 +22,8 @@ pub struct Plain<Lit: Literal> {
     γ: Γ<Lit>,
     /// CNF we're working on.
     δ: Cnf<Lit>,
+    /// True if the *pure* rule is active.
+    pure: bool,
 }
 
 implem! {
@@ -46,30 +49,38 @@ impl<Lit: Literal> Plain<Lit> {
         Self {
             γ: Γ::new(),
             δ: f.into_cnf(),
+            pure: false,
         }
     }
+
+    /// (De)activates the *pure* rule, inactive by default.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
 }
 
 impl<Lit: Literal> Plain<Lit> {
     /// *Assume* rule.
-    pub fn assume(&self, lit: Lit) -> Res<Self, Lit> {
+    pub fn assume(&self, lit: Lit, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("assume({})", lit);
         let mut new: Self = self.clone();
         let is_new = new.insert(lit);
 
         if is_new {
-            new.bcp()
+            new.bcp(stats)
         } else {
             panic!("trying to assume a literal twice")
         }
     }
 
     /// *BCP* rule.
-    pub fn bcp(&self) -> Res<Self, Lit> {
+    pub fn bcp(&self, stats: &mut Stats) -> Res<Self, Lit> {
         log::debug!("bcp(), γ.len(): {}", self.γ.len());
         let mut new = Self {
             γ: self.γ.clone(),
             δ: Cnf::with_capacity(self.δ.len()),
+            pure: self.pure,
         };
         let mut new_clause = Clause::with_capacity(5);
 
@@ -89,8 +100,15 @@ impl<Lit: Literal> Plain<Lit> {
             }
 
             match new_clause.len() {
-                0 => raise!(unsat),
-                1 => new = new.assume(new_clause.drain(0..).next().expect("unreachable"))?,
+                0 => {
+                    stats.conflicts += 1;
+                    raise!(unsat)
+                }
+                1 => {
+                    stats.propagations += 1;
+                    let lit = new_clause.drain(0..).next().expect("unreachable");
+                    new = new.assume(lit, stats)?
+                }
                 _ => {
                     // Got a new disjunction, add it to the new CNF.
                     new_clause.shrink_to_fit();
@@ -102,40 +120,82 @@ impl<Lit: Literal> Plain<Lit> {
         Ok(new)
     }
 
-    pub fn unsat(&self) -> Res<Empty, Lit> {
+    /// *Pure* rule, assumes the literals whose negation does not appear in `δ`.
+    ///
+    /// Yields `None` if the rule is inactive or there are no pure literals. Assuming pure literals
+    /// only drops clauses, which can make other literals pure: the rule is applied until there are
+    /// none left.
+    pub fn pure(&self, stats: &mut Stats) -> Option<Self> {
+        if !self.pure {
+            return None;
+        }
+        let mut pure = crate::pure_literals(self.δ.iter());
+        if pure.is_empty() {
+            return None;
+        }
+        let mut new = self.clone();
+        while !pure.is_empty() {
+            log::debug!("pure(), {} literals", pure.len());
+            stats.pure += pure.len();
+            new.δ
+                .retain(|clause| !clause.iter().any(|lit| pure.contains(lit)));
+            new.γ.extend(pure);
+            pure = crate::pure_literals(new.δ.iter());
+        }
+        Some(new)
+    }
+
+    /// *Unsat* rule, `depth` is the number of decisions leading to `self`.
+    ///
+    /// Applies the [*pure* rule][Self::pure] (if active) before deciding. Raises an unknown result
+    /// if `cancel` is cancelled before a decision, which is made by `branch`.
+    pub fn unsat(
+        &self,
+        depth: usize,
+        stats: &mut Stats,
+        cancel: &Cancel,
+        branch: &mut (dyn Branch<Lit> + '_),
+    ) -> Res<Empty, Lit> {
         log::debug!("unsat()");
         if self.δ.is_empty() {
             raise!(sat self.γ.clone())
+        } else if cancel.is_cancelled() {
+            raise!(unknown)
+        } else if let Some(new) = self.pure(stats) {
+            new.unsat(depth, stats, cancel, branch)
         } else {
-            let disj = &self.δ[0];
-            if let Some(lit) = disj.iter().next() {
-                match self.assume(lit.clone()).and_then(|new| new.unsat()) {
-                    Ok(empty) => match empty {},
-                    Err(e) => {
-                        if e.is_unsat() {
-                            ()
-                        } else {
-                            return Err(e);
-                        }
+            let lit = branch.pick(&mut self.δ.iter());
+            stats.decision(depth + 1);
+            match self
+                .assume(lit.clone(), stats)
+                .and_then(|new| new.unsat(depth + 1, stats, cancel, branch))
+            {
+                Ok(empty) => match empty {},
+                Err(e) => {
+                    if e.is_unsat() {
+                        // No dependency tracking, the decision alone is blamed.
+                        branch.conflict(&[lit.ref_negate()])
+                    } else {
+                        return Err(e);
                     }
                 }
+            }
 
-                let n_lit = lit.ref_negate();
-                log::trace!("backtracking {}", lit);
-                let new = self.assume(n_lit)?;
-                let empty = new.unsat()?;
+            let n_lit = lit.ref_negate();
+            log::trace!("backtracking {}", lit);
+            let new = self.assume(n_lit, stats)?;
+            let empty = new.unsat(depth + 1, stats, cancel, branch)?;
 
-                match empty {}
-            } else {
-                panic!("illegal empty disjunct in application of `unsat` rule")
-            }
+            match empty {}
         }
     }
 
-    pub fn solve(&self) -> Out<Lit> {
-        match self.unsat() {
+    pub fn solve(&self, branch: &mut (dyn Branch<Lit> + '_), cancel: &Cancel) -> (Out<Lit>, Stats) {
+        let mut stats = Stats::new();
+        let res = match self.unsat(0, &mut stats, cancel, branch) {
             Err(res) => res,
             Ok(empty) => match empty {},
-        }
+        };
+        (res, stats)
     }
 }
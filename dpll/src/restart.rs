@@ -0,0 +1,230 @@
+//! Restart policies of the CDCL solvers, see [`DpllImpl::has_restarts`].
+//!
+//! A restart undoes all decisions but keeps the clauses learned so far, so that the search starts
+//! over with more knowledge and can escape a bad early decision. Policies decide when to restart
+//! based on the conflicts of the search. The paper never restarts, which is [`Restarts::Never`].
+
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+/// Enumerates the restart policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Restarts {
+    /// Never restart, as in the paper.
+    #[default]
+    Never,
+    /// Restarts after `unit · luby(i)` conflicts, with `luby` the [Luby sequence].
+    ///
+    /// [Luby sequence]: https://oeis.org/A182105
+    Luby(usize),
+    /// Restarts after some conflicts, the interval grows geometrically from its initial value.
+    Geometric(usize),
+    /// Glucose-style: restarts when the recent learned clauses have a high LBD compared to all
+    /// learned clauses, see [`Policy::conflict`].
+    Glucose,
+}
+implem! {
+    for Restarts {
+        Display {
+            |&self, fmt| match self {
+                Self::Never => "never".fmt(fmt),
+                Self::Luby(unit) => write!(fmt, "luby:{}", unit),
+                Self::Geometric(first) => write!(fmt, "geometric:{}", first),
+                Self::Glucose => "glucose".fmt(fmt),
+            }
+        }
+    }
+}
+impl Restarts {
+    /// Default unit of [`Self::Luby`] and initial interval of [`Self::Geometric`].
+    pub const DEFAULT_INTERVAL: usize = 100;
+
+    pub const NAMES: &'static [(&'static str, &'static str)] = &[
+        ("never", "Never restart, as in the paper"),
+        (
+            "luby[:<unit>]",
+            "Luby sequence of intervals times <unit> conflicts, <unit> defaults to `100`",
+        ),
+        (
+            "geometric[:<first>]",
+            "Intervals growing by 1.5 from <first> conflicts, <first> defaults to `100`",
+        ),
+        (
+            "glucose",
+            "Restart when recent learned clauses have a high LBD",
+        ),
+    ];
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, interval) = match name.split_once(':') {
+            Some((name, interval)) => (name, Some(interval.parse().ok().filter(|n| *n > 0)?)),
+            None => (name, None),
+        };
+        match (name, interval) {
+            ("never", None) => Some(Self::Never),
+            ("luby", interval) => Some(Self::Luby(interval.unwrap_or(Self::DEFAULT_INTERVAL))),
+            ("geometric", interval) => {
+                Some(Self::Geometric(interval.unwrap_or(Self::DEFAULT_INTERVAL)))
+            }
+            ("glucose", None) => Some(Self::Glucose),
+            _ => None,
+        }
+    }
+
+    /// Creates the policy.
+    pub fn build(self) -> Policy {
+        Policy::new(self)
+    }
+}
+
+/// `i`-th element of the Luby sequence `1, 1, 2, 1, 1, 2, 4, 1, ...`, starting at `0`.
+fn luby(mut i: usize) -> usize {
+    // Find the smallest complete subsequence containing `i`, and its size.
+    let (mut size, mut seq) = (1, 0);
+    while size < i + 1 {
+        seq += 1;
+        size = 2 * size + 1
+    }
+    // Go down the subsequences until `i` is the last element of one of them.
+    while size - 1 != i {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        i %= size
+    }
+    1 << seq
+}
+
+/// State of a restart policy.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    restarts: Restarts,
+    /// Conflicts since the last restart.
+    conflicts: usize,
+    /// Number of conflicts triggering the next restart, for interval-based policies.
+    limit: usize,
+    /// Number of restarts so far.
+    count: usize,
+    /// LBDs of the most recent learned clauses, for [`Restarts::Glucose`].
+    recent: VecDeque<usize>,
+    /// Sum of `recent`.
+    recent_sum: usize,
+    /// Sum of the LBDs of all learned clauses.
+    total_sum: usize,
+    /// Number of learned clauses.
+    total_count: usize,
+}
+impl Policy {
+    /// Growth factor of [`Restarts::Geometric`] intervals.
+    const GEOMETRIC_FACTOR: f64 = 1.5;
+    /// Number of recent learned clauses [`Restarts::Glucose`] considers.
+    const GLUCOSE_WINDOW: usize = 50;
+    /// [`Restarts::Glucose`] restarts when the recent average LBD times this factor exceeds the
+    /// global average.
+    const GLUCOSE_K: f64 = 0.8;
+
+    /// Constructor.
+    pub fn new(restarts: Restarts) -> Self {
+        let limit = match restarts {
+            Restarts::Never | Restarts::Glucose => usize::MAX,
+            Restarts::Luby(unit) => unit.saturating_mul(luby(0)),
+            Restarts::Geometric(first) => first,
+        };
+        Self {
+            restarts,
+            conflicts: 0,
+            limit,
+            count: 0,
+            recent: VecDeque::with_capacity(Self::GLUCOSE_WINDOW),
+            recent_sum: 0,
+            total_sum: 0,
+            total_count: 0,
+        }
+    }
+
+    /// Registers a conflict, true if the solver should restart now.
+    ///
+    /// `lbd` is the *Literal Block Distance* of the clause learned from the conflict, *i.e.* the
+    /// number of distinct decision levels among its literals. Only [`Restarts::Glucose`] uses it.
+    /// When the result is true, the policy assumes the solver restarts.
+    pub fn conflict(&mut self, lbd: usize) -> bool {
+        self.conflicts += 1;
+        let restart = match self.restarts {
+            Restarts::Never => false,
+            Restarts::Luby(_) | Restarts::Geometric(_) => self.conflicts >= self.limit,
+            Restarts::Glucose => {
+                self.total_sum += lbd;
+                self.total_count += 1;
+                self.recent.push_back(lbd);
+                self.recent_sum += lbd;
+                if self.recent.len() > Self::GLUCOSE_WINDOW {
+                    self.recent_sum -= self.recent.pop_front().expect("unreachable");
+                }
+                self.recent.len() == Self::GLUCOSE_WINDOW && {
+                    let recent_avg = self.recent_sum as f64 / self.recent.len() as f64;
+                    let total_avg = self.total_sum as f64 / self.total_count as f64;
+                    recent_avg * Self::GLUCOSE_K > total_avg
+                }
+            }
+        };
+        if restart {
+            self.restarted()
+        }
+        restart
+    }
+
+    /// Updates the state of the policy after a restart.
+    fn restarted(&mut self) {
+        self.count += 1;
+        self.conflicts = 0;
+        match self.restarts {
+            Restarts::Never => (),
+            Restarts::Luby(unit) => self.limit = unit.saturating_mul(luby(self.count)),
+            Restarts::Geometric(_) => {
+                // Make sure small intervals grow too.
+                self.limit = ((self.limit as f64 * Self::GEOMETRIC_FACTOR) as usize)
+                    .max(self.limit.saturating_add(1))
+            }
+            Restarts::Glucose => {
+                self.recent.clear();
+                self.recent_sum = 0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luby_sequence() {
+        let seq: Vec<_> = (0..15).map(luby).collect();
+        assert_eq!(seq, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    /// Number of conflicts between the first restarts of some policy.
+    fn intervals(restarts: Restarts, count: usize) -> Vec<usize> {
+        let mut policy = restarts.build();
+        let mut res = Vec::with_capacity(count);
+        let mut conflicts = 0;
+        while res.len() < count {
+            conflicts += 1;
+            if policy.conflict(1) {
+                res.push(conflicts);
+                conflicts = 0
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn geometric_intervals() {
+        assert_eq!(intervals(Restarts::Geometric(1), 6), [1, 2, 3, 4, 6, 9]);
+        assert_eq!(intervals(Restarts::Geometric(100), 3), [100, 150, 225]);
+    }
+
+    #[test]
+    fn luby_intervals() {
+        assert_eq!(intervals(Restarts::Luby(2), 7), [2, 2, 4, 2, 2, 4, 8]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Small deterministic pseudo-random number generator.
+//!
+//! Used by the [random branching heuristic][crate::branch::Random] and by the benchmark generators
+//! of the frontend, so that runs only depend on their seed and not on the version of some external
+//! crate.
+
+/// SplitMix64 pseudo-random number generator.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    /// Constructor.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    /// Next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// Random integer in `0..n`, `n` must be positive.
+    pub fn below(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0);
+        (self.next_u64() % n as u64) as usize
+    }
+    /// Random boolean.
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+    /// Moves `count` random elements of `elems` to its front, in random order.
+    pub fn partial_shuffle<T>(&mut self, elems: &mut [T], count: usize) {
+        for idx in 0..count.min(elems.len()) {
+            let other = idx + self.below(elems.len() - idx);
+            elems.swap(idx, other)
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Statistics over the rule applications of a DPLL run.
+
+use crate::prelude::*;
+
+/// Statistics of a DPLL run.
+///
+/// All variants count the same events, so that they can be compared. Decisions are applications
+/// of the *unsat* rule (branching on a literal), and propagations are literals assumed by the
+/// *bcp* rule. Pure literals are literals assumed by the optional *pure* rule. Levels are
+/// *backjumped* when a conflict does not depend on the decision of a level, which is skipped
+/// instead of exploring its second branch. Restarts undo all decisions, see [`crate::restart`], and
+/// reductions delete learned clauses, see [`crate::learned`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of decisions.
+    pub decisions: usize,
+    /// Number of literals assumed by propagation.
+    pub propagations: usize,
+    /// Number of literals assumed by the *pure* rule.
+    pub pure: usize,
+    /// Number of conflicts, *i.e.* clauses falsified by the environment.
+    pub conflicts: usize,
+    /// Number of learned clauses, only non-zero for CDCL variants.
+    pub learned: usize,
+    /// Number of learned clauses deleted, only non-zero for CDCL variants with a maximum number of
+    /// learned clauses.
+    pub deleted: usize,
+    /// Number of restarts, only non-zero for CDCL variants with a restart policy.
+    pub restarts: usize,
+    /// Maximum number of nested decisions.
+    pub max_depth: usize,
+    /// Total number of decision levels skipped by backjumping.
+    pub backjumped: usize,
+}
+implem! {
+    for Stats {
+        Display {
+            |&self, fmt| write!(
+                fmt,
+                "decisions: {}, propagations: {}, pure literals: {}, conflicts: {}, learned: {}, \
+                deleted: {}, restarts: {}, max depth: {}, backjumped levels: {}",
+                self.decisions,
+                self.propagations,
+                self.pure,
+                self.conflicts,
+                self.learned,
+                self.deleted,
+                self.restarts,
+                self.max_depth,
+                self.backjumped,
+            )
+        }
+    }
+}
+impl Stats {
+    /// Constructor, all statistics are zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a decision at some depth, `1` for the first decision.
+    pub fn decision(&mut self, depth: usize) {
+        self.decisions += 1;
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    /// Accumulates the statistics of another run.
+    pub fn merge(&mut self, other: &Self) {
+        self.decisions += other.decisions;
+        self.propagations += other.propagations;
+        self.pure += other.pure;
+        self.conflicts += other.conflicts;
+        self.learned += other.learned;
+        self.deleted += other.deleted;
+        self.restarts += other.restarts;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.backjumped += other.backjumped;
+    }
+}
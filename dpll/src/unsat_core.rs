@@ -0,0 +1,369 @@
+//! Unsat core extraction.
+//!
+//! Cores are extracted from proofs of unsatisfiability. Solvers log the lemmas they learn to a
+//! [`Trace`], which is then replayed backward from the empty clause: each lemma needed to derive
+//! the empty clause is checked by unit propagation, and the original clauses involved in these
+//! propagations form the core.
+//!
+//! Since proofs only exist for the [`Backjump`](Dpll::Backjump) and [`Cdcl`](Dpll::Cdcl) variants
+//! (and the imperative solver), so do cores.
+
+use crate::{prelude::*, proof::Proof};
+
+/// A proof step.
+#[derive(Debug, Clone)]
+enum Step<Lit> {
+    Add(Vec<Lit>),
+    Delete(Vec<Lit>),
+}
+
+/// Proof that's kept in memory, used to extract unsat cores.
+#[derive(Debug, Clone)]
+pub struct Trace<Lit> {
+    steps: Vec<Step<Lit>>,
+}
+impl<Lit> Trace<Lit> {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self { steps: vec![] }
+    }
+
+    /// Lemmas of the proof, deletions are ignored.
+    pub fn lemmas(&self) -> impl Iterator<Item = &[Lit]> + '_ {
+        self.steps.iter().filter_map(|step| match step {
+            Step::Add(lits) => Some(lits.as_slice()),
+            Step::Delete(_) => None,
+        })
+    }
+}
+impl<Lit> Default for Trace<Lit> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Lit: Clone> Proof<Lit> for Trace<Lit> {
+    fn add(&mut self, lits: &[Lit]) {
+        self.steps.push(Step::Add(lits.to_vec()))
+    }
+    fn delete(&mut self, lits: &[Lit]) {
+        self.steps.push(Step::Delete(lits.to_vec()))
+    }
+}
+
+/// Extracts an unsat core from a proof of unsatisfiability of `cnf`.
+///
+/// Yields the (sorted) indices of the clauses of `cnf` in the core. Fails if `trace` does not
+/// derive the empty clause, or if one of its lemmas does not follow from the previous clauses by
+/// unit propagation.
+pub fn extract<Lit: Literal>(cnf: &Cnf<Lit>, trace: &Trace<Lit>) -> Result<Vec<usize>, String> {
+    let mut db = Db::new();
+    for (idx, clause) in cnf.iter().enumerate() {
+        if clause.is_empty() {
+            return Ok(vec![idx]);
+        }
+        let _ = db.add(clause.iter());
+    }
+
+    // Forward pass, up to the empty clause, remembers which clause each step adds or deletes.
+    let mut events = Vec::with_capacity(trace.steps.len());
+    let mut refuted = false;
+    for step in &trace.steps {
+        match step {
+            Step::Add(lits) => {
+                events.push(Ok(db.add(lits.iter())));
+                if lits.is_empty() {
+                    refuted = true;
+                    break;
+                }
+            }
+            Step::Delete(lits) => {
+                if let Some(id) = db.remove(lits.iter()) {
+                    events.push(Err(id))
+                }
+            }
+        }
+    }
+    if !refuted {
+        return Err("[internal] proof does not derive the empty clause".into());
+    }
+
+    // Backward pass.
+    if let Some(Ok(id)) = events.last() {
+        db.clauses[*id].marked = true
+    }
+    for event in events.into_iter().rev() {
+        match event {
+            Err(id) => db.clauses[id].active = true,
+            Ok(id) => {
+                db.clauses[id].active = false;
+                if db.clauses[id].marked && !db.rup(id) {
+                    return Err(format!(
+                        "[internal] lemma #{} does not follow from unit propagation",
+                        id - cnf.len(),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok((0..cnf.len())
+        .filter(|idx| db.clauses[*idx].marked)
+        .collect())
+}
+
+/// A clause of the database.
+#[derive(Debug, Clone)]
+struct DbClause {
+    /// Literal codes, see [`Db::code`], the first two are watched if the clause is not a tautology.
+    lits: Vec<usize>,
+    /// False if the clause is deleted.
+    active: bool,
+    /// True if the clause is needed to derive the empty clause.
+    marked: bool,
+}
+
+/// Clause database with two watched literals unit propagation.
+///
+/// All propagations start from an empty assignment, which means any two literals of a clause are
+/// valid watches when propagation starts. In particular, (de)activating clauses does not require
+/// updating the watches.
+struct Db<Lit: Literal> {
+    /// Maps literals that are smaller than their negation to their variable.
+    vars: Map<Lit, usize>,
+    clauses: Vec<DbClause>,
+    /// Maps literal codes to the clauses watching them.
+    watches: Vec<Vec<usize>>,
+    /// Unit clauses.
+    units: Vec<usize>,
+    /// Maps normalized clauses to the active clauses they correspond to.
+    index: Map<Vec<usize>, Vec<usize>>,
+    /// Maps variables to their value, if any.
+    values: Vec<Option<bool>>,
+    /// Maps variables to the clause that propagated them, if any.
+    reasons: Vec<Option<usize>>,
+    /// Maps variables to a flag used when marking the clauses involved in a conflict.
+    seen: Vec<bool>,
+    /// Literal codes assigned to true, in order.
+    trail: Vec<usize>,
+    /// Index of the next literal to propagate in the trail.
+    qhead: usize,
+}
+impl<Lit: Literal> Db<Lit> {
+    fn new() -> Self {
+        Self {
+            vars: Map::new(),
+            clauses: vec![],
+            watches: vec![],
+            units: vec![],
+            index: Map::new(),
+            values: vec![],
+            reasons: vec![],
+            seen: vec![],
+            trail: vec![],
+            qhead: 0,
+        }
+    }
+
+    /// Code of a literal: the index of its variable times two, plus one if the literal is bigger
+    /// than its negation.
+    ///
+    /// Creates the variable if needed.
+    fn code(&mut self, lit: &Lit) -> usize {
+        let nlit = lit.ref_negate();
+        let (rep, neg) = if *lit <= nlit {
+            (lit.clone(), false)
+        } else {
+            (nlit, true)
+        };
+        let var = match self.vars.get(&rep) {
+            Some(var) => *var,
+            None => {
+                let var = self.values.len();
+                let _ = self.vars.insert(rep, var);
+                self.watches.push(vec![]);
+                self.watches.push(vec![]);
+                self.values.push(None);
+                self.reasons.push(None);
+                self.seen.push(false);
+                var
+            }
+        };
+        (var << 1) | neg as usize
+    }
+
+    /// Normalized version of a clause: sorted literal codes without duplicates.
+    fn normalize<'a>(&mut self, lits: impl Iterator<Item = &'a Lit>) -> Vec<usize>
+    where
+        Lit: 'a,
+    {
+        let mut codes: Vec<usize> = lits.map(|lit| self.code(lit)).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        codes
+    }
+
+    fn value(&self, code: usize) -> Option<bool> {
+        self.values[code >> 1].map(|val| val != (code & 1 == 1))
+    }
+
+    /// Adds a clause, yields its identifier.
+    fn add<'a>(&mut self, lits: impl Iterator<Item = &'a Lit>) -> usize
+    where
+        Lit: 'a,
+    {
+        let id = self.clauses.len();
+        let lits = self.normalize(lits);
+        let tautology = lits.windows(2).any(|pair| pair[0] >> 1 == pair[1] >> 1);
+        if !tautology {
+            match lits.len() {
+                0 => (),
+                1 => self.units.push(id),
+                _ => {
+                    self.watches[lits[0]].push(id);
+                    self.watches[lits[1]].push(id);
+                }
+            }
+        }
+        self.index.entry(lits.clone()).or_default().push(id);
+        self.clauses.push(DbClause {
+            lits,
+            active: true,
+            marked: false,
+        });
+        id
+    }
+
+    /// Deactivates a clause, yields its identifier if it was found.
+    fn remove<'a>(&mut self, lits: impl Iterator<Item = &'a Lit>) -> Option<usize>
+    where
+        Lit: 'a,
+    {
+        let lits = self.normalize(lits);
+        let id = self.index.get_mut(&lits)?.pop()?;
+        self.clauses[id].active = false;
+        Some(id)
+    }
+
+    fn assign(&mut self, code: usize, reason: Option<usize>) {
+        self.values[code >> 1] = Some(code & 1 == 0);
+        self.reasons[code >> 1] = reason;
+        self.trail.push(code)
+    }
+
+    /// Unit propagation, yields the conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_code = self.trail[self.qhead] ^ 1;
+            self.qhead += 1;
+            let mut watches = std::mem::take(&mut self.watches[false_code]);
+            let mut conflict = None;
+
+            let mut idx = 0;
+            'watches: while idx < watches.len() {
+                let id = watches[idx];
+                let clause = &mut self.clauses[id];
+                if !clause.active {
+                    idx += 1;
+                    continue 'watches;
+                }
+                if clause.lits[0] == false_code {
+                    clause.lits.swap(0, 1)
+                }
+                let first = clause.lits[0];
+                let first_value = self.values[first >> 1].map(|val| val != (first & 1 == 1));
+                if first_value == Some(true) {
+                    idx += 1;
+                    continue 'watches;
+                }
+                for new_idx in 2..clause.lits.len() {
+                    let code = clause.lits[new_idx];
+                    if self.values[code >> 1].map(|val| val != (code & 1 == 1)) != Some(false) {
+                        clause.lits.swap(1, new_idx);
+                        self.watches[code].push(id);
+                        let _ = watches.swap_remove(idx);
+                        continue 'watches;
+                    }
+                }
+                if first_value == Some(false) {
+                    conflict = Some(id);
+                    break 'watches;
+                }
+                self.assign(first, Some(id));
+                idx += 1;
+            }
+
+            self.watches[false_code] = watches;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Marks all clauses involved in deriving the literals of a clause, including the clause.
+    fn mark(&mut self, id: usize) {
+        let mut todo = vec![id];
+        while let Some(id) = todo.pop() {
+            self.clauses[id].marked = true;
+            for code in &self.clauses[id].lits {
+                let var = code >> 1;
+                if !self.seen[var] {
+                    self.seen[var] = true;
+                    if let Some(reason) = self.reasons[var] {
+                        todo.push(reason)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that clause `id` is a *reverse unit propagation* consequence of the active clauses.
+    ///
+    /// On success, marks the clauses involved.
+    fn rup(&mut self, id: usize) -> bool {
+        let conflict = self.rup_conflict(id);
+        match conflict {
+            Some(Ok(conflict)) => self.mark(conflict),
+            Some(Err(var)) => {
+                if let Some(reason) = self.reasons[var] {
+                    self.mark(reason)
+                }
+            }
+            None => (),
+        }
+        for code in self.trail.drain(0..) {
+            self.values[code >> 1] = None;
+            self.seen[code >> 1] = false;
+        }
+        self.qhead = 0;
+        conflict.is_some()
+    }
+
+    /// Propagates the unit clauses and the negation of clause `id`.
+    ///
+    /// Yields the conflicting clause if any, or the variable of a literal of clause `id` that's
+    /// true after propagating the unit clauses.
+    fn rup_conflict(&mut self, id: usize) -> Option<Result<usize, usize>> {
+        for idx in 0..self.units.len() {
+            let unit = self.units[idx];
+            if !self.clauses[unit].active {
+                continue;
+            }
+            let code = self.clauses[unit].lits[0];
+            match self.value(code) {
+                Some(true) => (),
+                Some(false) => return Some(Ok(unit)),
+                None => self.assign(code, Some(unit)),
+            }
+        }
+        for idx in 0..self.clauses[id].lits.len() {
+            let code = self.clauses[id].lits[idx];
+            match self.value(code) {
+                Some(true) => return Some(Err(code >> 1)),
+                Some(false) => (),
+                None => self.assign(code ^ 1, None),
+            }
+        }
+        self.propagate().map(Ok)
+    }
+}
@@ -0,0 +1,371 @@
+//! Benchmark generators: uniform random k-SAT and crafted families.
+//!
+//! Generation is deterministic given a seed, using the small [PRNG][Rng] of `dpll` so that the
+//! instances do not depend on the version of some external crate. Each [`Bench`] knows its
+//! expected status when the family guarantees one.
+
+use std::io::Write;
+
+pub use dpll::rng::Rng;
+
+use crate::prelude::*;
+
+/// A generated benchmark.
+#[derive(Debug, Clone)]
+pub struct Bench {
+    /// Name of the benchmark, can be used as a file name.
+    pub name: String,
+    pub cnf: Cnf<Lit>,
+    /// Expected satisfiability, `None` if unknown.
+    pub expected: Option<bool>,
+}
+impl Bench {
+    /// Expected status as a string: `sat`, `unsat` or `unknown`.
+    pub fn status(&self) -> &'static str {
+        match self.expected {
+            Some(true) => "sat",
+            Some(false) => "unsat",
+            None => "unknown",
+        }
+    }
+    /// Writes the benchmark in the SAT-comp format.
+    ///
+    /// The CNF is preceded by comments giving its name and its expected status, the latter as
+    /// `c expected: <status>`.
+    pub fn write(&self, w: impl Write) -> Res<()> {
+        let mut w = crate::write::Writer::new(w);
+        w.comment(&self.name)?;
+        w.comment(format!("expected: {}", self.status()))?;
+        w.cnf(&self.cnf)?;
+        let _ = w.finish()?;
+        Ok(())
+    }
+}
+
+/// Benchmark families.
+#[derive(Debug, Clone)]
+pub enum Family {
+    /// Uniform random k-SAT, `k` distinct variables per clause.
+    Random {
+        k: usize,
+        vars: usize,
+        /// Clause/variable ratio.
+        ratio: f64,
+    },
+    /// `holes + 1` pigeons in `holes` holes, always unsat.
+    Pigeonhole { holes: usize },
+    /// Tseitin parity formula over a `width × height` grid, with random vertex charges whose sum
+    /// is even iff `sat`.
+    Parity {
+        width: usize,
+        height: usize,
+        sat: bool,
+    },
+    /// Coloring of a random graph.
+    ///
+    /// If `planted`, the graph only has edges compatible with a hidden coloring and is sat.
+    Coloring {
+        vertices: usize,
+        edges: usize,
+        colors: usize,
+        planted: bool,
+    },
+    /// N-queens, sat iff `size` is not `2` or `3`.
+    Queens { size: usize },
+}
+impl Family {
+    /// Family names and descriptions.
+    pub const NAMES: &'static [(&'static str, &'static str)] = &[
+        (
+            "random",
+            "Uniform random k-SAT at a given clause/variable ratio",
+        ),
+        ("pigeonhole", "Pigeonhole principle, unsat"),
+        (
+            "parity",
+            "Tseitin parity formulas over a grid, sat or unsat",
+        ),
+        ("coloring", "Random graph coloring, sat if planted"),
+        ("queens", "N-queens, sat iff n is not 2 or 3"),
+    ];
+
+    /// True if the seed has an influence on the instances of this family.
+    pub fn is_random(&self) -> bool {
+        match self {
+            Self::Random { .. } | Self::Parity { .. } | Self::Coloring { .. } => true,
+            Self::Pigeonhole { .. } | Self::Queens { .. } => false,
+        }
+    }
+
+    /// Generates an instance.
+    pub fn generate(&self, seed: u64) -> Res<Bench> {
+        let mut rng = Rng::new(seed);
+        match *self {
+            Self::Random { k, vars, ratio } => random(&mut rng, k, vars, ratio).map(|cnf| Bench {
+                name: format!("random-{}-sat-v{}-r{}-s{}", k, vars, ratio, seed),
+                cnf,
+                expected: None,
+            }),
+            Self::Pigeonhole { holes } => pigeonhole(holes).map(|cnf| Bench {
+                name: format!("pigeonhole-{}", holes),
+                cnf,
+                expected: Some(false),
+            }),
+            Self::Parity { width, height, sat } => {
+                parity(&mut rng, width, height, sat).map(|cnf| Bench {
+                    name: format!(
+                        "parity-{}x{}-{}-s{}",
+                        width,
+                        height,
+                        if sat { "sat" } else { "unsat" },
+                        seed
+                    ),
+                    cnf,
+                    expected: Some(sat),
+                })
+            }
+            Self::Coloring {
+                vertices,
+                edges,
+                colors,
+                planted,
+            } => coloring(&mut rng, vertices, edges, colors, planted).map(|cnf| Bench {
+                name: format!(
+                    "coloring-v{}-e{}-c{}{}-s{}",
+                    vertices,
+                    edges,
+                    colors,
+                    if planted { "-planted" } else { "" },
+                    seed
+                ),
+                cnf,
+                expected: if planted { Some(true) } else { None },
+            }),
+            Self::Queens { size } => queens(size).map(|cnf| Bench {
+                name: format!("queens-{}", size),
+                cnf,
+                expected: Some(size != 2 && size != 3),
+            }),
+        }
+    }
+}
+
+/// Positive literal of variable `idx`, must be positive.
+fn pos(idx: usize) -> Lit {
+    Lit::new(idx, false)
+}
+/// Negative literal of variable `idx`, must be positive.
+fn neg(idx: usize) -> Lit {
+    Lit::new(idx, true)
+}
+
+/// At-most-one constraint over some variables, pairwise encoding.
+fn at_most_one(clauses: &mut Vec<Clause<Lit>>, vars: &[usize]) {
+    for (idx, v1) in vars.iter().enumerate() {
+        for v2 in &vars[idx + 1..] {
+            clauses.push(Clause::new(vec![neg(*v1), neg(*v2)]))
+        }
+    }
+}
+
+/// Uniform random k-SAT.
+pub fn random(rng: &mut Rng, k: usize, vars: usize, ratio: f64) -> Res<Cnf<Lit>> {
+    if k == 0 {
+        bail!("illegal clause length `0`")
+    }
+    if k > vars {
+        bail!(
+            "cannot generate clauses of length {} over {} variable(s)",
+            k,
+            vars
+        )
+    }
+    if !ratio.is_finite() || ratio < 0. {
+        bail!("illegal clause/variable ratio `{}`", ratio)
+    }
+    let clause_count = (ratio * vars as f64).round() as usize;
+    let mut all_vars: Vec<usize> = (1..=vars).collect();
+    let clauses = (0..clause_count)
+        .map(|_| {
+            rng.partial_shuffle(&mut all_vars, k);
+            Clause::new(
+                all_vars[..k]
+                    .iter()
+                    .map(|idx| Lit::new(*idx, rng.bool()))
+                    .collect(),
+            )
+        })
+        .collect();
+    Ok(Cnf::new(clauses))
+}
+
+/// Pigeonhole principle: `holes + 1` pigeons, `holes` holes.
+pub fn pigeonhole(holes: usize) -> Res<Cnf<Lit>> {
+    if holes == 0 {
+        bail!("pigeonhole instances need at least one hole")
+    }
+    let var = |pigeon: usize, hole: usize| pigeon * holes + hole + 1;
+    let mut clauses = Vec::new();
+    for pigeon in 0..=holes {
+        clauses.push(Clause::new(
+            (0..holes).map(|hole| pos(var(pigeon, hole))).collect(),
+        ))
+    }
+    for hole in 0..holes {
+        let pigeons: Vec<_> = (0..=holes).map(|pigeon| var(pigeon, hole)).collect();
+        at_most_one(&mut clauses, &pigeons)
+    }
+    Ok(Cnf::new(clauses))
+}
+
+/// Tseitin parity formula over a `width × height` grid.
+///
+/// Edges are the variables, each vertex requires the XOR of its edges to be equal to its charge.
+/// The grid is connected, so the formula is sat iff the sum of the charges is even.
+pub fn parity(rng: &mut Rng, width: usize, height: usize, sat: bool) -> Res<Cnf<Lit>> {
+    if width == 0 || height == 0 {
+        bail!(
+            "parity instances need a non-empty grid, got {}x{}",
+            width,
+            height
+        )
+    }
+    let vertex_count = width * height;
+    let mut incident: Vec<Vec<usize>> = vec![vec![]; vertex_count];
+    let mut edge_count = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let vertex = y * width + x;
+            let mut neighbors = vec![];
+            if x + 1 < width {
+                neighbors.push(vertex + 1)
+            }
+            if y + 1 < height {
+                neighbors.push(vertex + width)
+            }
+            for neighbor in neighbors {
+                edge_count += 1;
+                incident[vertex].push(edge_count);
+                incident[neighbor].push(edge_count);
+            }
+        }
+    }
+
+    let mut charges: Vec<bool> = (0..vertex_count).map(|_| rng.bool()).collect();
+    let odd = charges.iter().filter(|charge| **charge).count() % 2 == 1;
+    if odd == sat {
+        let last = vertex_count - 1;
+        charges[last] = !charges[last]
+    }
+
+    let mut clauses = Vec::new();
+    for (edges, charge) in incident.iter().zip(charges) {
+        // Block all assignments of the edges with the wrong parity.
+        for assignment in 0..(1usize << edges.len()) {
+            if (assignment.count_ones() % 2 == 1) != charge {
+                clauses.push(Clause::new(
+                    edges
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, edge)| Lit::new(*edge, assignment & (1 << idx) != 0))
+                        .collect(),
+                ))
+            }
+        }
+    }
+    Ok(Cnf::new(clauses))
+}
+
+/// Coloring of a random graph with `edges` distinct edges.
+///
+/// If `planted`, edges are only drawn between vertices of different colors w.r.t. a random hidden
+/// coloring, so that the instance is sat.
+pub fn coloring(
+    rng: &mut Rng,
+    vertices: usize,
+    edges: usize,
+    colors: usize,
+    planted: bool,
+) -> Res<Cnf<Lit>> {
+    if colors == 0 {
+        bail!("coloring instances need at least one color")
+    }
+    let hidden: Vec<usize> = (0..vertices).map(|_| rng.below(colors)).collect();
+    let mut candidates = Vec::new();
+    for v1 in 0..vertices {
+        for v2 in v1 + 1..vertices {
+            if !planted || hidden[v1] != hidden[v2] {
+                candidates.push((v1, v2))
+            }
+        }
+    }
+    if edges > candidates.len() {
+        bail!(
+            "cannot draw {} edge(s), only {} are possible{}",
+            edges,
+            candidates.len(),
+            if planted {
+                " given the hidden coloring"
+            } else {
+                ""
+            },
+        )
+    }
+    rng.partial_shuffle(&mut candidates, edges);
+
+    let var = |vertex: usize, color: usize| vertex * colors + color + 1;
+    let mut clauses = Vec::new();
+    for vertex in 0..vertices {
+        let vars: Vec<_> = (0..colors).map(|color| var(vertex, color)).collect();
+        clauses.push(Clause::new(vars.iter().cloned().map(pos).collect()));
+        at_most_one(&mut clauses, &vars)
+    }
+    for (v1, v2) in &candidates[..edges] {
+        for color in 0..colors {
+            clauses.push(Clause::new(vec![
+                neg(var(*v1, color)),
+                neg(var(*v2, color)),
+            ]))
+        }
+    }
+    Ok(Cnf::new(clauses))
+}
+
+/// N-queens: one queen per row, no two queens attacking each other.
+pub fn queens(size: usize) -> Res<Cnf<Lit>> {
+    if size == 0 {
+        bail!("n-queens instances need a non-empty board")
+    }
+    let var = |row: usize, col: usize| row * size + col + 1;
+    let mut clauses = Vec::new();
+    for row in 0..size {
+        let vars: Vec<_> = (0..size).map(|col| var(row, col)).collect();
+        clauses.push(Clause::new(vars.iter().cloned().map(pos).collect()));
+        at_most_one(&mut clauses, &vars)
+    }
+    for col in 0..size {
+        let vars: Vec<_> = (0..size).map(|row| var(row, col)).collect();
+        at_most_one(&mut clauses, &vars)
+    }
+    // Diagonals, `diag` is `row - col + size - 1` going down and `row + col` going up.
+    for diag in 0..2 * size - 1 {
+        let down: Vec<_> = (0..size)
+            .filter_map(|row| {
+                (row + size - 1)
+                    .checked_sub(diag)
+                    .filter(|col| *col < size)
+                    .map(|col| var(row, col))
+            })
+            .collect();
+        at_most_one(&mut clauses, &down);
+        let up: Vec<_> = (0..size)
+            .filter_map(|row| {
+                diag.checked_sub(row)
+                    .filter(|col| *col < size)
+                    .map(|col| var(row, col))
+            })
+            .collect();
+        at_most_one(&mut clauses, &up);
+    }
+    Ok(Cnf::new(clauses))
+}
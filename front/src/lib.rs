@@ -26,7 +26,9 @@ pub mod prelude {
     }
 }
 
+pub mod gen;
 pub mod parse;
+pub mod write;
 
 use crate::prelude::*;
 
@@ -39,6 +41,14 @@ impl Lit {
     pub fn new(idx: usize, neg: bool) -> Self {
         Self { idx, neg }
     }
+    /// Index of the variable of this literal.
+    pub fn idx(&self) -> usize {
+        self.idx
+    }
+    /// True if the literal is negative.
+    pub fn is_neg(&self) -> bool {
+        self.neg
+    }
 }
 implem! {
     for Lit {
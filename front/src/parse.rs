@@ -1,4 +1,7 @@
 //! SAT-comp format parser.
+//!
+//! Inputs can be compressed with xz, gzip, bzip2 or zstd, see [`Compression`]: the compression
+//! format is detected from the first bytes of the input, not from the file extension.
 
 use std::{
     fs::{File, OpenOptions},
@@ -10,14 +13,171 @@ use xz2::bufread::XzDecoder;
 
 use crate::prelude::*;
 
+/// Compression formats supported by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Not compressed.
+    Plain,
+    Xz,
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+implem! {
+    for Compression {
+        Display {
+            |&self, fmt| match self {
+                Self::Plain => "uncompressed".fmt(fmt),
+                Self::Xz => "xz".fmt(fmt),
+                Self::Gzip => "gzip".fmt(fmt),
+                Self::Bzip2 => "bzip2".fmt(fmt),
+                Self::Zstd => "zstd".fmt(fmt),
+            }
+        }
+    }
+}
+impl Compression {
+    /// Number of bytes [`Self::sniff`] needs to recognize all formats.
+    pub const MAGIC_LEN: usize = 6;
+
+    /// Detects the compression format of some input from its first bytes (*magic bytes*).
+    pub fn sniff(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Self::Xz
+        } else if bytes.starts_with(&[0x1F, 0x8B]) {
+            Self::Gzip
+        } else if bytes.starts_with(b"BZh") {
+            Self::Bzip2
+        } else if bytes.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Self::Zstd
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+/// How the parser treats inputs that do not respect their `p cnf` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderCheck {
+    /// Literals over undeclared variables and clause count mismatches are errors.
+    Strict,
+    /// Literals over undeclared variables and clause count mismatches are logged as warnings.
+    #[default]
+    Lenient,
+}
+
 /// SAT-comp CNF parser.
+///
+/// Works on a stream of tokens: clauses can span several lines, and a line can contain several
+/// clauses. Lines starting with `c` are comments, and a line starting with `%` ends the formula
+/// (as in SATLIB benchmarks).
 pub struct Parser<R: Read> {
     reader: BufReader<R>,
     line_buf: String,
+    /// Current line, starting at `1`.
     line: usize,
-    #[allow(dead_code)]
-    lit_count: usize,
+    /// Byte offset of the next token in `line_buf`.
+    cursor: usize,
+    /// Number of variables declared in the header.
+    var_count: usize,
+    /// Number of clauses declared in the header.
+    clause_count: usize,
+    header_check: HeaderCheck,
+    /// True if a literal over an undeclared variable has been reported, used in lenient mode to
+    /// only warn once.
+    undeclared_reported: bool,
     cnf: Cnf<Lit>,
+    /// Line each clause of `cnf` was parsed from.
+    clause_lines: Vec<usize>,
+}
+
+/// Result of parsing, a CNF with the line each of its clauses starts on.
+#[derive(Debug, Clone)]
+pub struct ParsedCnf {
+    cnf: Cnf<Lit>,
+    clause_lines: Vec<usize>,
+    /// Number of variables declared in the header.
+    var_count: usize,
+    /// Number of clauses declared in the header.
+    clause_count: usize,
+}
+implem! {
+    for ParsedCnf {
+        Deref<Target = Cnf<Lit>> {
+            |&self| &self.cnf,
+            |&mut self| &mut self.cnf,
+        }
+    }
+}
+impl ParsedCnf {
+    /// CNF accessor, note that `Self` already [`Deref`]s to [`Cnf<Lit>`].
+    pub fn cnf(&self) -> &Cnf<Lit> {
+        &self.cnf
+    }
+    /// Number of variables declared in the `p cnf` header.
+    pub fn var_count(&self) -> usize {
+        self.var_count
+    }
+    /// Number of clauses declared in the `p cnf` header.
+    pub fn clause_count(&self) -> usize {
+        self.clause_count
+    }
+    /// Variables declared in the `p cnf` header that do not appear in any clause, in increasing
+    /// order.
+    pub fn unused_vars(&self) -> Vec<usize> {
+        let mut used = vec![false; self.var_count + 1];
+        for clause in self.cnf.iter() {
+            for lit in clause.iter() {
+                if let Some(used) = used.get_mut(lit.idx()) {
+                    *used = true
+                }
+            }
+        }
+        (1..=self.var_count).filter(|idx| !used[*idx]).collect()
+    }
+    /// Line the clause at index `clause_idx` starts on.
+    pub fn line_of(&self, clause_idx: usize) -> Option<usize> {
+        self.clause_lines.get(clause_idx).cloned()
+    }
+
+    /// Checks that a model satisfies all clauses.
+    ///
+    /// Fails on the first clause falsified by `model`, reporting its line.
+    pub fn check_model(&self, model: &Set<Lit>) -> Res<()> {
+        if let Some(idx) = self.cnf.first_falsified(model) {
+            bail!(
+                "model falsifies clause `{}` on line {}",
+                self.cnf[idx],
+                self.clause_lines[idx],
+            )
+        }
+        Ok(())
+    }
+
+    /// Completes a (partial) model so that it mentions all variables declared in the header, and
+    /// all variables appearing in the clauses.
+    ///
+    /// Variables absent from `model` are assigned to false.
+    pub fn complete_model(&self, model: &mut Set<Lit>) {
+        let max_idx = self
+            .cnf
+            .iter()
+            .flat_map(|clause| clause.iter())
+            .map(Lit::idx)
+            .fold(self.var_count, usize::max);
+        for idx in 1..=max_idx {
+            let lit = Lit::new(idx, false);
+            if !model.contains(&lit) {
+                let _ = model.insert(lit.negate());
+            }
+        }
+    }
+}
+impl Formula for ParsedCnf {
+    type Lit = Lit;
+    fn into_cnf(self) -> Cnf<Lit> {
+        self.cnf
+    }
 }
 
 impl Parser<File> {
@@ -41,196 +201,268 @@ impl Parser<XzDecoder<BufReader<File>>> {
     }
 }
 
-impl<R: Read> Parser<R> {
-    /// Puts the first line from `reader` that's not a comment in `line_buf`.
-    ///
-    /// Clears `line_buf`.
-    ///
-    /// Return the number of comment lines read, or `0` if EOI was reached, potentially after
-    /// parsing some comment lines.
-    fn read_line(reader: &mut BufReader<R>, line_buf: &mut String) -> Res<usize> {
-        let mut cnt = 0;
-        loop {
-            line_buf.clear();
-            let bytes_read = reader
-                .read_line(line_buf)
-                .chain_err(|| "while reading first line")?;
-            if bytes_read == 0 || line_buf.trim() == "0" {
-                break Ok(0);
-            } else {
-                cnt += 1;
-                if !line_buf.is_empty() && (&line_buf[0..1] == "c" || &line_buf[0..1] == "%") {
-                    // Comment line, move on.
-                    continue;
-                } else {
-                    break Ok(cnt);
-                }
-            }
+impl Parser<Box<dyn Read>> {
+    /// Opens a file, or stdin if `path` is `-`, decompressing it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Res<Self> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            Self::decompress(std::io::stdin()).chain_err(|| "while reading stdin")
+        } else {
+            let file = OpenOptions::new()
+                .read(true)
+                .open(path)
+                .chain_err(|| format!("while opening file `{}`", path.display()))?;
+            Self::decompress(file).chain_err(|| format!("while reading file `{}`", path.display()))
         }
     }
-    /// Constructor.
-    pub fn new(reader: R) -> Res<Self> {
-        let mut reader = BufReader::new(reader);
-        let mut line_buf = String::with_capacity(17);
 
-        const PREF: &str = "p cnf";
+    /// Constructor for a possibly compressed input, see [`Compression::sniff`].
+    pub fn decompress(reader: impl Read + 'static) -> Res<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut magic = Vec::with_capacity(Compression::MAGIC_LEN);
+        let _ = reader
+            .by_ref()
+            .take(Compression::MAGIC_LEN as u64)
+            .read_to_end(&mut magic)?;
+        let compression = Compression::sniff(&magic);
+        log::debug!("input is {}", compression);
+        let reader = std::io::Cursor::new(magic).chain(reader);
+        let reader: Box<dyn Read> = match compression {
+            Compression::Plain => Box::new(reader),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new(reader)),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        };
+        Self::new(reader).chain_err(|| format!("while parsing {} input", compression))
+    }
+}
 
-        macro_rules! err {
-            {} => {
-                format!(
-                    "error on first non-comment line, expected `{}<int> <int>` format", PREF,
-                )
-            };
-        }
-        macro_rules! bail {
-            {} => {
-                return Err(err!().into())
-            };
-        }
+impl<R: Read> Parser<R> {
+    /// Maximum number of clauses preallocated, the header is not trusted until parsing ends.
+    const MAX_PREALLOC: usize = 1 << 16;
 
-        let lines_read = Self::read_line(&mut reader, &mut line_buf)?;
-        if lines_read == 0 {
-            bail!()
-        }
+    /// Constructor, parses the `p cnf` header.
+    pub fn new(reader: R) -> Res<Self> {
+        let mut parser = Self {
+            reader: BufReader::new(reader),
+            line_buf: String::with_capacity(80),
+            line: 0,
+            cursor: 0,
+            var_count: 0,
+            clause_count: 0,
+            header_check: HeaderCheck::default(),
+            undeclared_reported: false,
+            cnf: Cnf::empty(),
+            clause_lines: Vec::new(),
+        };
+        parser.header()?;
+        let capacity = parser.clause_count.min(Self::MAX_PREALLOC);
+        parser.cnf = Cnf::with_capacity(capacity);
+        parser.clause_lines = Vec::with_capacity(capacity);
+        Ok(parser)
+    }
 
-        log::trace!("parsing first CNF line");
+    /// Sets how the parser treats inputs that do not respect their header, lenient by default.
+    pub fn header_check(mut self, header_check: HeaderCheck) -> Self {
+        self.header_check = header_check;
+        self
+    }
 
-        if line_buf.len() < PREF.len() {
-            bail!()
-        } else if &line_buf[0..PREF.len()] != PREF {
-            bail!()
+    /// Fails in strict mode, logs a warning otherwise.
+    fn header_mismatch(&self, msg: String) -> Res<()> {
+        match self.header_check {
+            HeaderCheck::Strict => bail!(msg),
+            HeaderCheck::Lenient => {
+                log::warn!("{}", msg);
+                Ok(())
+            }
         }
+    }
 
-        log::trace!("prefix okay");
-
-        let start = PREF.len();
-
-        let txt = &line_buf[start..];
-        log::trace!("parsing tail `{}`", txt.trim());
-        let mut parser = DisjParser::new(txt);
-        parser.space(1).chain_err(|| err!())?;
-        let lit_count = parser.usize().chain_err(|| err!())?;
-        log::trace!("lit_count is {}", lit_count);
-        log::trace!("parsing tail `{}`", parser.txt.trim());
-        parser.space(1).chain_err(|| err!())?;
-        let disj_count = parser.usize().chain_err(|| err!())?;
-        log::trace!("disj_count is {}", disj_count);
-
-        Ok(Self {
-            reader,
-            line_buf,
-            line: lines_read,
-            lit_count,
-            cnf: Cnf::with_capacity(disj_count),
-        })
+    /// Column of a byte offset of the current line, starting at `1`.
+    fn col(&self, offset: usize) -> usize {
+        self.line_buf[..offset].chars().count() + 1
     }
 
-    pub fn fail(&self, msg: impl Display) -> err::Error {
+    /// Error at some byte offset of the current line.
+    fn fail(&self, offset: usize, msg: impl Display) -> err::Error {
         format!(
-            "error line {}: {} `{}`",
+            "error on line {}, column {}: {}",
             self.line,
-            msg,
-            self.line_buf.trim()
+            self.col(offset),
+            msg
         )
         .into()
     }
 
-    fn parse_clause(&mut self) -> Res<()> {
-        let mut mini_parser = DisjParser::new(&self.line_buf);
-        let mut clause = Clause::with_capacity(7);
-        mini_parser.space(0)?;
-        // Line loaded.
-        'read_lit: loop {
-            match mini_parser.lit()? {
-                Some(lit) => {
-                    log::trace!("parsed a lit: {}", lit);
-                    clause.push(lit);
-                    mini_parser.space(1)?;
-                }
-                None => break 'read_lit,
-            }
-        }
-        self.cnf.push(clause);
-        Ok(())
-    }
-
-    pub fn parse(mut self) -> Res<Cnf<Lit>> {
+    /// Loads the next line that's not blank nor a comment in `line_buf`.
+    ///
+    /// Returns `false` on EOI, or if the line starts with `%`.
+    fn next_line(&mut self) -> Res<bool> {
         loop {
-            log::trace!("parsing line {}", self.line);
             self.line_buf.clear();
-            let lines_read = Self::read_line(&mut self.reader, &mut self.line_buf)?;
-            if lines_read == 0 {
-                // EOF reached.
-                break;
-            } else {
-                self.line += lines_read;
+            let bytes_read = self
+                .reader
+                .read_line(&mut self.line_buf)
+                .chain_err(|| format!("while reading line {}", self.line + 1))?;
+            if bytes_read == 0 {
+                return Ok(false);
+            }
+            self.line += 1;
+            let txt = self.line_buf.trim_start();
+            self.cursor = self.line_buf.len() - txt.len();
+            match txt.chars().next() {
+                // Blank or comment line, move on.
+                None | Some('c') => continue,
+                Some('%') => return Ok(false),
+                Some(_) => return Ok(true),
             }
+        }
+    }
 
-            self.parse_clause()
-                .chain_err(|| self.fail("while parsing this line"))?;
+    /// Byte range of the next token of the current line, if any.
+    fn token(&mut self) -> Option<(usize, usize)> {
+        let txt = &self.line_buf[self.cursor..];
+        let start = self.cursor + (txt.len() - txt.trim_start().len());
+        let len = self.line_buf[start..]
+            .find(char::is_whitespace)
+            .unwrap_or(self.line_buf.len() - start);
+        self.cursor = start + len;
+        if len == 0 {
+            None
+        } else {
+            Some((start, start + len))
         }
-        Ok(self.cnf)
     }
-}
 
-struct DisjParser<'txt> {
-    txt: &'txt str,
-    cursor: usize,
-}
-impl<'txt> DisjParser<'txt> {
-    fn new(txt: &'txt str) -> Self {
-        Self { txt, cursor: 0 }
-    }
-
-    fn space(&mut self, min: usize) -> Res<()> {
-        for (idx, c) in self.txt[self.cursor..].chars().enumerate() {
-            if c.is_whitespace() {
-                self.cursor += c.len_utf8()
-            } else if idx < min {
-                bail!("expected space character ` `, got end of line")
-            } else {
-                break;
+    /// Parses the `p cnf <int> <int>` header, must be the first line that's not a comment.
+    fn header(&mut self) -> Res<()> {
+        const EXPECTED: &str = "expected `p cnf <int> <int>` header";
+        if !self.next_line()? {
+            bail!("{}, got end of input", EXPECTED)
+        }
+        log::trace!("parsing header `{}`", self.line_buf.trim());
+
+        let mut ints = [0; 2];
+        for idx in 0..4 {
+            let (start, end) = match self.token() {
+                Some(range) => range,
+                None => {
+                    let eol = self.line_buf.trim_end().len();
+                    return Err(self.fail(eol, format!("{}, got end of line", EXPECTED)));
+                }
+            };
+            let token = &self.line_buf[start..end];
+            match idx {
+                0 if token == "p" => (),
+                1 if token == "cnf" => (),
+                0 | 1 => return Err(self.fail(start, format!("{}, got `{}`", EXPECTED, token))),
+                _ => match token.parse() {
+                    Ok(int) => ints[idx - 2] = int,
+                    Err(_) => {
+                        return Err(self.fail(start, format!("expected integer, got `{}`", token)))
+                    }
+                },
             }
         }
+        if let Some((start, end)) = self.token() {
+            return Err(self.fail(
+                start,
+                format!("unexpected `{}` after header", &self.line_buf[start..end]),
+            ));
+        }
+
+        let [var_count, clause_count] = ints;
+        log::trace!("var_count is {}", var_count);
+        log::trace!("clause_count is {}", clause_count);
+        self.var_count = var_count;
+        self.clause_count = clause_count;
         Ok(())
     }
-    fn usize(&mut self) -> Res<usize> {
-        let end = self.txt[self.cursor..]
-            .chars()
-            .take_while(|c| c.is_ascii_digit())
-            .fold(self.cursor, |acc, c| acc + c.len_utf8());
-        let n = usize::from_str_radix(&self.txt[self.cursor..end], 10)
-            .chain_err(|| "illegal usize value")?;
-        self.cursor = end;
-        Ok(n)
-    }
-    fn lit(&mut self) -> Res<Option<Lit>> {
-        let (start, negated) = match self.txt[self.cursor..].chars().next() {
-            Some('-') => (self.cursor + 1, true),
-            Some(c) if c.is_ascii_digit() => (self.cursor, false),
-            Some(c) => bail!("expected literal (`-` or digit), got `{}`", c),
-            None => bail!("expected literal, got end of line"),
+
+    /// Parses a literal token, `None` if the token is `0`.
+    fn lit(&self, start: usize, end: usize) -> Res<Option<Lit>> {
+        let token = &self.line_buf[start..end];
+        let (negated, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token),
         };
-        self.cursor = start;
-        let mut chars = self.txt[start..].chars();
-        loop {
-            match chars.next() {
-                Some(c) if c.is_ascii_digit() => self.cursor += 1,
-                None | Some(_) => break,
-            }
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.fail(start, format!("expected literal, got `{}`", token)));
         }
-        let idx_str = &self.txt[start..self.cursor];
-        let idx = match usize::from_str_radix(idx_str, 10) {
+        let idx: usize = match digits.parse() {
             Ok(idx) => idx,
-            Err(_) => bail!("expected literal UID, found `{}`", idx_str),
+            Err(_) => return Err(self.fail(start, format!("illegal literal `{}`", token))),
         };
         if idx == 0 {
             if negated {
-                bail!("unexpected negated `0`, illegal end of line marker")
+                return Err(self.fail(
+                    start,
+                    "unexpected negated `0`, illegal end of clause marker",
+                ));
             }
             Ok(None)
         } else {
             Ok(Some(Lit::new(idx, negated)))
         }
     }
+
+    pub fn parse(mut self) -> Res<ParsedCnf> {
+        let mut clause = Clause::with_capacity(7);
+        // Line the current clause starts on, `None` if no literal of this clause was parsed yet.
+        let mut clause_line = None;
+
+        while self.next_line()? {
+            log::trace!("parsing line {}", self.line);
+            while let Some((start, end)) = self.token() {
+                match self.lit(start, end)? {
+                    None => {
+                        let clause = std::mem::replace(&mut clause, Clause::with_capacity(7));
+                        self.cnf.push(clause);
+                        self.clause_lines
+                            .push(clause_line.take().unwrap_or(self.line));
+                    }
+                    Some(lit) => {
+                        log::trace!("parsed a lit: {}", lit);
+                        if lit.idx() > self.var_count && !self.undeclared_reported {
+                            self.header_mismatch(format!(
+                                "literal `{}` on line {}, column {} exceeds the {} variable(s) \
+                                declared in the header",
+                                lit,
+                                self.line,
+                                self.col(start),
+                                self.var_count,
+                            ))?;
+                            self.undeclared_reported = true;
+                        }
+                        if clause_line.is_none() {
+                            clause_line = Some(self.line)
+                        }
+                        clause.push(lit);
+                    }
+                }
+            }
+        }
+
+        if let Some(line) = clause_line {
+            bail!(
+                "unterminated clause starting on line {}, expected `0` before end of input",
+                line
+            )
+        }
+        if self.cnf.len() != self.clause_count {
+            self.header_mismatch(format!(
+                "found {} clause(s), but the header declares {}",
+                self.cnf.len(),
+                self.clause_count,
+            ))?
+        }
+        Ok(ParsedCnf {
+            cnf: self.cnf,
+            clause_lines: self.clause_lines,
+            var_count: self.var_count,
+            clause_count: self.clause_count,
+        })
+    }
 }
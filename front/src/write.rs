@@ -0,0 +1,105 @@
+//! SAT-comp format writer.
+//!
+//! Literals are written from their variable index and sign, not from their [`Display`]
+//! implementation, so that the output is valid DIMACS whatever [`Display`] does.
+
+use std::io::Write;
+
+use crate::prelude::*;
+
+/// Largest variable index appearing in some clauses, *i.e.* the variable count of their header.
+pub fn var_count<'a>(clauses: impl IntoIterator<Item = &'a Clause<Lit>>) -> usize {
+    clauses
+        .into_iter()
+        .flat_map(|clause| clause.iter())
+        .map(Lit::idx)
+        .max()
+        .unwrap_or(0)
+}
+
+/// SAT-comp CNF writer.
+pub struct Writer<W: Write> {
+    writer: W,
+}
+impl<W: Write> Writer<W> {
+    /// Constructor.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Flushes the underlying writer and returns it.
+    pub fn finish(mut self) -> Res<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a comment, one `c` line for each line of `txt`.
+    pub fn comment(&mut self, txt: impl Display) -> Res<()> {
+        for line in txt.to_string().lines() {
+            if line.is_empty() {
+                writeln!(self.writer, "c")?
+            } else {
+                writeln!(self.writer, "c {}", line)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a `p cnf` header.
+    pub fn header(&mut self, var_count: usize, clause_count: usize) -> Res<()> {
+        writeln!(self.writer, "p cnf {} {}", var_count, clause_count)?;
+        Ok(())
+    }
+
+    /// Writes a clause, *i.e.* its literals followed by `0`.
+    pub fn clause<'a>(&mut self, lits: impl IntoIterator<Item = &'a Lit>) -> Res<()> {
+        for lit in lits {
+            if lit.idx() == 0 {
+                bail!("illegal literal over variable `0`")
+            }
+            if lit.is_neg() {
+                write!(self.writer, "-{} ", lit.idx())?
+            } else {
+                write!(self.writer, "{} ", lit.idx())?
+            }
+        }
+        writeln!(self.writer, "0")?;
+        Ok(())
+    }
+
+    /// Writes a CNF and its header.
+    pub fn cnf(&mut self, cnf: &Cnf<Lit>) -> Res<()> {
+        self.header(var_count(cnf.iter()), cnf.len())?;
+        for clause in cnf.iter() {
+            self.clause(clause.iter())?
+        }
+        Ok(())
+    }
+
+    /// Writes a labeled CNF and its header.
+    ///
+    /// The labels of each clause are written as a comment preceding it.
+    pub fn lcnf(&mut self, lcnf: &LCnf<Lit>) -> Res<()> {
+        self.header(var_count(lcnf.iter().map(|c| c.clause())), lcnf.len())?;
+        for clause in lcnf.iter() {
+            if !clause.labels().is_empty() {
+                let mut labels: Vec<_> = clause.labels().iter().collect();
+                labels.sort();
+                self.comment(
+                    labels
+                        .into_iter()
+                        .fold(String::from("labels:"), |mut acc, lit| {
+                            acc.push(' ');
+                            if lit.is_neg() {
+                                acc.push('-')
+                            }
+                            acc.push_str(&lit.idx().to_string());
+                            acc
+                        }),
+                )?
+            }
+            self.clause(clause.iter())?
+        }
+        Ok(())
+    }
+}
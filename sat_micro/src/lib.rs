@@ -1,5 +1,6 @@
 //! Sat-micro API.
 
 pub extern crate base;
+pub extern crate checker;
 pub extern crate dpll;
 pub extern crate front;
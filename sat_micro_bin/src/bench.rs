@@ -0,0 +1,313 @@
+//! Benchmark mode, runs DPLL variants over many files and writes the results as CSV and JSON.
+//!
+//! Runs are sequential so that they do not disturb each other's time and memory usage. Memory
+//! usage is the peak heap usage during solving on top of the heap usage before it, see
+//! [`crate::mem`]. Timeouts cancel the solver, see [`Cancel`].
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+use sat_micro::{
+    dpll,
+    front::{parse::ParsedCnf, prelude::*},
+};
+
+use crate::{conf::Conf2, mem};
+
+/// Extensions of the files considered as benchmarks.
+const EXTENSIONS: &[&str] = &["cnf", "xz", "gz", "bz2", "zst"];
+
+/// Answer of a run.
+pub enum Answer {
+    Sat,
+    Unsat,
+    Timeout,
+    /// Solver or parse error, or model check failure.
+    Error(String),
+}
+impl Answer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sat => "sat",
+            Self::Unsat => "unsat",
+            Self::Timeout => "timeout",
+            Self::Error(_) => "error",
+        }
+    }
+    fn error(&self) -> Option<&str> {
+        match self {
+            Self::Error(msg) => Some(msg),
+            Self::Sat | Self::Unsat | Self::Timeout => None,
+        }
+    }
+}
+
+/// Result of running a DPLL variant on a file.
+pub struct Record {
+    pub file: String,
+    pub dpll: DpllImpl,
+    pub answer: Answer,
+    /// Solving time, parsing excluded.
+    pub time: Duration,
+    /// Solver statistics, `None` on errors.
+    pub stats: Option<Stats>,
+    /// Peak heap usage in bytes, `None` on parse errors.
+    pub peak_mem: Option<usize>,
+}
+impl Record {
+    fn new(file: String, dpll: DpllImpl, answer: Answer, time: Duration) -> Self {
+        Self {
+            file,
+            dpll,
+            answer,
+            time,
+            stats: None,
+            peak_mem: None,
+        }
+    }
+}
+
+/// Benchmark files in a directory and its sub-directories, sorted.
+pub fn files(dir: &Path) -> Res<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut todo = vec![dir.to_path_buf()];
+    while let Some(dir) = todo.pop() {
+        for entry in
+            std::fs::read_dir(&dir).chain_err(|| format!("while opening `{}`", dir.display()))?
+        {
+            let path = entry
+                .chain_err(|| format!("while reading an entry of `{}`", dir.display()))?
+                .path();
+            let is_bench = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext));
+            if path.is_dir() {
+                todo.push(path)
+            } else if is_bench {
+                files.push(path)
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Runs some DPLL variants on all benchmarks of a directory.
+///
+/// The timeout of `conf`, if any, applies to each run.
+pub fn run(conf: &Conf2, dir: &str, variants: &[DpllImpl]) -> Res<Vec<Record>> {
+    let files = files(Path::new(dir))?;
+    if files.is_empty() {
+        bail!("no benchmark found in `{}`", dir)
+    }
+    println!(
+        "c running {} variant(s) on {} file(s)",
+        variants.len(),
+        files.len()
+    );
+
+    let mut records = Vec::with_capacity(files.len() * variants.len());
+    for path in &files {
+        let file = path.display().to_string();
+        let cnf = match crate::parse_file(path, conf.header_check) {
+            Ok(cnf) => Arc::new(cnf),
+            Err(e) => {
+                let msg = error_to_string(&e);
+                println!("c parse error on `{}`: {}", file, msg);
+                for dpll in variants {
+                    records.push(Record::new(
+                        file.clone(),
+                        *dpll,
+                        Answer::Error(msg.clone()),
+                        Duration::ZERO,
+                    ))
+                }
+                continue;
+            }
+        };
+        for dpll in variants {
+            let record = run_one(conf, file.clone(), &cnf, *dpll);
+            println!(
+                "c {: >18} | {: ^7} | {: >15.9} seconds | {}",
+                dpll.short_name(),
+                record.answer.name(),
+                record.time.as_secs_f64(),
+                file,
+            );
+            records.push(record)
+        }
+    }
+    Ok(records)
+}
+
+fn error_to_string(e: &err::Error) -> String {
+    e.iter().fold(String::new(), |mut acc, e| {
+        if !acc.is_empty() {
+            acc.push_str(": ")
+        }
+        acc.push_str(&e.to_string());
+        acc
+    })
+}
+
+fn run_one(conf: &Conf2, file: String, cnf: &Arc<ParsedCnf>, dpll: DpllImpl) -> Record {
+    let (sender, recver) = mpsc::channel();
+    let thread_cnf = cnf.clone();
+
+    let baseline = mem::reset_peak();
+    let start = Instant::now();
+    let cancel = conf.timeout_ms.map_or_else(Cancel::new, |millis| {
+        Cancel::with_deadline(start + Duration::from_millis(millis))
+    });
+    let options = conf.options(dpll);
+    let preprocess = conf.preprocess;
+    // Solving in a separate thread so that panics are reported as crashes. Preprocessing is part
+    // of the run, models are reconstructed before being checked.
+    let _ = std::thread::spawn(move || {
+        let res = if preprocess {
+            let pre = dpll::preprocess::preprocess(thread_cnf.cnf().clone());
+            dpll::solve(pre.cnf().clone(), dpll, &options, &cancel).map(|(res, stats)| {
+                let res = match res {
+                    dpll::Outcome::Sat(model) => dpll::Outcome::Sat(pre.reconstruct(model)),
+                    res => res,
+                };
+                (res, stats)
+            })
+        } else {
+            dpll::solve(thread_cnf.cnf().clone(), dpll, &options, &cancel)
+        };
+        let _ = sender.send((res, Instant::now()));
+    });
+    let received = recver.recv();
+    let peak_mem = mem::peak().saturating_sub(baseline);
+
+    let (answer, stats, time) = match received {
+        Ok((Ok((dpll::Outcome::Sat(model), stats)), end)) => {
+            let answer = match crate::sat_action(conf.check_models, cnf, &model) {
+                Ok(_) => Answer::Sat,
+                Err(e) => Answer::Error(error_to_string(&e)),
+            };
+            (answer, Some(stats), end - start)
+        }
+        Ok((Ok((dpll::Outcome::Unsat(()), stats)), end)) => {
+            (Answer::Unsat, Some(stats), end - start)
+        }
+        Ok((Ok((dpll::Outcome::Unknown, stats)), end)) => {
+            (Answer::Timeout, Some(stats), end - start)
+        }
+        Ok((Err(e), end)) => (Answer::Error(e), None, end - start),
+        Err(mpsc::RecvError) => (
+            Answer::Error("solver crashed".into()),
+            None,
+            start.elapsed(),
+        ),
+    };
+    Record {
+        stats,
+        peak_mem: Some(peak_mem),
+        ..Record::new(file, dpll, answer, time)
+    }
+}
+
+/// Column names of the CSV and JSON outputs.
+const COLUMNS: [&str; 15] = [
+    "file",
+    "variant",
+    "result",
+    "time",
+    "decisions",
+    "propagations",
+    "pure",
+    "conflicts",
+    "learned",
+    "deleted",
+    "restarts",
+    "max_depth",
+    "backjumped",
+    "peak_mem",
+    "error",
+];
+
+/// Values of a record, `None` for missing values and quoted strings for string values.
+fn values(record: &Record, quote: impl Fn(&str) -> String) -> [Option<String>; 15] {
+    let stat = |get: fn(&Stats) -> usize| record.stats.as_ref().map(|s| get(s).to_string());
+    [
+        Some(quote(&record.file)),
+        Some(quote(record.dpll.short_name())),
+        Some(quote(record.answer.name())),
+        Some(record.time.as_secs_f64().to_string()),
+        stat(|s| s.decisions),
+        stat(|s| s.propagations),
+        stat(|s| s.pure),
+        stat(|s| s.conflicts),
+        stat(|s| s.learned),
+        stat(|s| s.deleted),
+        stat(|s| s.restarts),
+        stat(|s| s.max_depth),
+        stat(|s| s.backjumped),
+        record.peak_mem.map(|n| n.to_string()),
+        record.answer.error().map(quote),
+    ]
+}
+
+/// Writes records as CSV, with a header line, missing values are empty.
+pub fn write_csv(mut w: impl Write, records: &[Record]) -> Res<()> {
+    fn quote(s: &str) -> String {
+        if s.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.into()
+        }
+    }
+    writeln!(w, "{}", COLUMNS.join(","))?;
+    for record in records {
+        let values = values(record, quote);
+        let line: Vec<_> = values
+            .iter()
+            .map(|value| value.as_deref().unwrap_or(""))
+            .collect();
+        writeln!(w, "{}", line.join(","))?
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Writes records as a JSON array of objects, missing values are `null`.
+pub fn write_json(mut w: impl Write, records: &[Record]) -> Res<()> {
+    fn quote(s: &str) -> String {
+        let mut res = String::with_capacity(s.len() + 2);
+        res.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => res.push_str("\\\""),
+                '\\' => res.push_str("\\\\"),
+                '\n' => res.push_str("\\n"),
+                '\r' => res.push_str("\\r"),
+                '\t' => res.push_str("\\t"),
+                c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+                c => res.push(c),
+            }
+        }
+        res.push('"');
+        res
+    }
+    writeln!(w, "[")?;
+    for (idx, record) in records.iter().enumerate() {
+        let values = values(record, quote);
+        let fields: Vec<_> = COLUMNS
+            .iter()
+            .zip(&values)
+            .map(|(col, value)| format!("\"{}\": {}", col, value.as_deref().unwrap_or("null")))
+            .collect();
+        let sep = if idx + 1 < records.len() { "," } else { "" };
+        writeln!(w, "  {{ {} }}{}", fields.join(", "), sep)?
+    }
+    writeln!(w, "]")?;
+    w.flush()?;
+    Ok(())
+}
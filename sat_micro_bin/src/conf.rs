@@ -4,20 +4,25 @@ use std::time::{Duration, Instant};
 
 use clap::Command;
 use log::LevelFilter;
-use sat_micro::{dpll, front::prelude::*};
+use sat_micro::{
+    checker, dpll,
+    front::{gen, parse::HeaderCheck, prelude::*},
+};
 
 pub type Matches = clap::ArgMatches;
 
-pub fn dpll_subcommands() -> impl Iterator<Item = Command> {
+/// DPLL variant subcommands supported by some DPLL implementation.
+pub fn dpll_subcommands(dpll_impl_name: &'static str) -> impl Iterator<Item = Command> {
     dpll::Dpll::NAMES
         .into_iter()
+        .filter(move |(name, _)| dpll::DpllImpl::from_name(dpll_impl_name, Some(name)).is_some())
         .map(|(name, about)| Command::new(name).about(*about))
 }
 pub fn dpll_impl_subcommands() -> impl Iterator<Item = Command> {
     dpll::DpllImpl::NAMES.into_iter().map(|(name, about)| {
         Command::new(name)
             .about(*about)
-            .subcommands(dpll_subcommands())
+            .subcommands(dpll_subcommands(name))
     })
 }
 pub fn dpll_impl_from_matches(matches: &Matches) -> Res<Option<dpll::DpllImpl>> {
@@ -41,17 +46,397 @@ pub fn dpll_impl_from_matches(matches: &Matches) -> Res<Option<dpll::DpllImpl>>
     }
 }
 
+/// Commands that do something else than solving the input file.
+pub enum Tool {
+    /// Checks a proof of unsatisfiability for the input file.
+    CheckProof {
+        proof: String,
+        format: checker::Format,
+    },
+    /// Re-emits the input file in a normalized form.
+    Dump {
+        /// Output file, stdout if `None`.
+        output: Option<String>,
+        /// If true, each clause is preceded by a comment giving its line in the input file.
+        lines: bool,
+    },
+    /// Shrinks the input file while the DPLL variants keep misbehaving on it.
+    Minimize {
+        /// Output file, stdout if `None`.
+        output: Option<String>,
+    },
+    /// Generates benchmarks, does not read the input file.
+    Gen {
+        family: gen::Family,
+        /// Seed of the first instance, incremented for each subsequent instance.
+        seed: u64,
+        /// Number of instances to generate.
+        count: usize,
+        /// Output file, or output directory if `count > 1`, stdout if `None`.
+        output: Option<String>,
+    },
+    /// Runs DPLL variants on all benchmarks of a directory, does not read the input file.
+    Bench {
+        dir: String,
+        variants: Vec<DpllImpl>,
+        /// CSV output file.
+        csv: Option<String>,
+        /// JSON output file.
+        json: Option<String>,
+    },
+}
+impl Tool {
+    /// True if the tool reads the input file.
+    pub fn reads_input(&self) -> bool {
+        match self {
+            Self::CheckProof { .. } | Self::Dump { .. } | Self::Minimize { .. } => true,
+            Self::Gen { .. } | Self::Bench { .. } => false,
+        }
+    }
+}
+
+pub fn check_proof_subcommand() -> Command {
+    use clap::Arg;
+    Command::new("check-proof")
+        .about("Checks a DRAT or LRAT proof of unsatisfiability for the input file")
+        .arg(
+            Arg::new("PROOF_FILE")
+                .required(true)
+                .help("Proof file, DRAT proofs can be textual or binary"),
+        )
+        .arg(
+            Arg::new("FORMAT")
+                .value_name("drat|lrat")
+                .long("format")
+                .num_args(1)
+                .value_parser(Conf1::validate_proof_format)
+                .help(
+                    "Specifies the format of the proof, deduced from the extension of the proof \
+                    file by default (`.lrat` for LRAT, DRAT otherwise)",
+                ),
+        )
+}
+pub fn dump_subcommand() -> Command {
+    use clap::Arg;
+    Command::new("dump")
+        .about(
+            "Re-emits the input file in the SAT-comp format, normalized: one clause per line, \
+            sorted literals without duplicates, and no comments",
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .value_name("FILE")
+                .long("output")
+                .short('o')
+                .num_args(1)
+                .help("Output file, stdout by default"),
+        )
+        .arg(
+            Arg::new("LINES")
+                .value_name("on|true|off|false")
+                .long("lines")
+                .num_args(1)
+                .value_parser(Conf1::validate_bool)
+                .default_value("off")
+                .help(
+                    "(De)activates comments giving the line each clause comes from in the input \
+                    file, [on|off|true|false]",
+                ),
+        )
+}
+pub fn minimize_subcommand() -> Command {
+    use clap::Arg;
+    Command::new("minimize")
+        .about(
+            "Removes clauses and literals from the input file while the DPLL variants disagree, \
+            crash, or produce wrong models, and writes the result in the SAT-comp format; \
+            `--timeout` bounds the time spent minimizing",
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .value_name("FILE")
+                .long("output")
+                .short('o')
+                .num_args(1)
+                .help("Output file, stdout by default"),
+        )
+}
+pub fn gen_subcommand() -> Command {
+    use clap::Arg;
+    fn usize_arg(
+        id: &'static str,
+        long: &'static str,
+        default: &'static str,
+        help: &'static str,
+    ) -> Arg {
+        Arg::new(id)
+            .value_name("INT")
+            .long(long)
+            .num_args(1)
+            .value_parser(Conf1::validate_usize)
+            .default_value(default)
+            .help(help)
+    }
+    fn bool_arg(
+        id: &'static str,
+        long: &'static str,
+        default: &'static str,
+        help: &'static str,
+    ) -> Arg {
+        Arg::new(id)
+            .value_name("on|true|off|false")
+            .long(long)
+            .num_args(1)
+            .value_parser(Conf1::validate_bool)
+            .default_value(default)
+            .help(help)
+    }
+    let family_subcommands = gen::Family::NAMES.iter().map(|(name, about)| {
+        let cmd = Command::new(*name)
+            .about(*about)
+            .arg(
+                Arg::new("SEED")
+                    .value_name("INT")
+                    .long("seed")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_u64)
+                    .default_value("0")
+                    .help("Seed of the first instance, incremented for each instance"),
+            )
+            .arg(usize_arg(
+                "COUNT",
+                "count",
+                "1",
+                "Number of instances to generate, more than one requires `--output`",
+            ))
+            .arg(
+                Arg::new("OUTPUT")
+                    .value_name("PATH")
+                    .long("output")
+                    .short('o')
+                    .num_args(1)
+                    .help(
+                        "Output file, or output directory when generating more than one instance, \
+                        stdout by default",
+                    ),
+            );
+        match *name {
+            "random" => cmd
+                .arg(usize_arg("K", "k", "3", "Number of literals per clause"))
+                .arg(usize_arg("VARS", "vars", "50", "Number of variables"))
+                .arg(
+                    Arg::new("RATIO")
+                        .value_name("FLOAT")
+                        .long("ratio")
+                        .num_args(1)
+                        .value_parser(Conf1::validate_ratio)
+                        .default_value("4.26")
+                        .help("Clause/variable ratio"),
+                ),
+            "pigeonhole" => cmd.arg(usize_arg("HOLES", "holes", "6", "Number of holes")),
+            "parity" => cmd
+                .arg(usize_arg("WIDTH", "width", "4", "Width of the grid"))
+                .arg(usize_arg("HEIGHT", "height", "4", "Height of the grid"))
+                .arg(bool_arg(
+                    "SAT",
+                    "sat",
+                    "on",
+                    "Generates sat instances if on, unsat ones otherwise, [on|off|true|false]",
+                )),
+            "coloring" => cmd
+                .arg(usize_arg(
+                    "VERTICES",
+                    "vertices",
+                    "20",
+                    "Number of vertices",
+                ))
+                .arg(usize_arg("EDGES", "edges", "40", "Number of edges"))
+                .arg(usize_arg("COLORS", "colors", "3", "Number of colors"))
+                .arg(bool_arg(
+                    "PLANTED",
+                    "planted",
+                    "off",
+                    "(De)activates hidden coloring planting, which makes instances sat, \
+                    [on|off|true|false]",
+                )),
+            "queens" => cmd.arg(usize_arg("SIZE", "size", "8", "Size of the board")),
+            _ => cmd,
+        }
+    });
+    Command::new("gen")
+        .about(
+            "Generates benchmarks in the SAT-comp format with their expected status, \
+            does not read the input file",
+        )
+        .subcommand_required(true)
+        .subcommands(family_subcommands)
+}
+pub fn gen_from_matches(matches: &Matches) -> Option<Tool> {
+    let (name, matches) = matches.subcommand()?;
+    let usize_of = |id: &str| -> usize {
+        *matches
+            .get_one(id)
+            .expect("arguments with default value cannot be absent")
+    };
+    let bool_of = |id: &str| -> bool {
+        *matches
+            .get_one(id)
+            .expect("arguments with default value cannot be absent")
+    };
+    let family = match name {
+        "random" => gen::Family::Random {
+            k: usize_of("K"),
+            vars: usize_of("VARS"),
+            ratio: *matches
+                .get_one("RATIO")
+                .expect("arguments with default value cannot be absent"),
+        },
+        "pigeonhole" => gen::Family::Pigeonhole {
+            holes: usize_of("HOLES"),
+        },
+        "parity" => gen::Family::Parity {
+            width: usize_of("WIDTH"),
+            height: usize_of("HEIGHT"),
+            sat: bool_of("SAT"),
+        },
+        "coloring" => gen::Family::Coloring {
+            vertices: usize_of("VERTICES"),
+            edges: usize_of("EDGES"),
+            colors: usize_of("COLORS"),
+            planted: bool_of("PLANTED"),
+        },
+        "queens" => gen::Family::Queens {
+            size: usize_of("SIZE"),
+        },
+        _ => return None,
+    };
+    Some(Tool::Gen {
+        family,
+        seed: *matches
+            .get_one("SEED")
+            .expect("arguments with default value cannot be absent"),
+        count: usize_of("COUNT"),
+        output: matches.get_one::<String>("OUTPUT").cloned(),
+    })
+}
+pub fn bench_subcommand() -> Command {
+    use clap::{Arg, ArgGroup};
+    Command::new("bench")
+        .about(
+            "Runs DPLL variants on all benchmarks of a directory, one at a time, and writes time, \
+            result and peak heap usage of each run as CSV and/or JSON; `--timeout` applies to \
+            each run, does not read the input file",
+        )
+        .arg(
+            Arg::new("DIR")
+                .required(true)
+                .help("Benchmark directory, searched recursively"),
+        )
+        .arg(
+            Arg::new("VARIANTS")
+                .value_name("IMPL/DPLL")
+                .long("variants")
+                .num_args(1..)
+                .value_delimiter(',')
+                .value_parser(Conf1::validate_dpll_impl)
+                .help("DPLL variants to run, all of them by default"),
+        )
+        .arg(
+            Arg::new("CSV")
+                .value_name("FILE")
+                .long("csv")
+                .num_args(1)
+                .help("CSV output file"),
+        )
+        .arg(
+            Arg::new("JSON")
+                .value_name("FILE")
+                .long("json")
+                .num_args(1)
+                .help("JSON output file"),
+        )
+        .group(
+            ArgGroup::new("OUTPUT")
+                .args(["CSV", "JSON"])
+                .required(true)
+                .multiple(true),
+        )
+}
+pub fn tool_from_matches(matches: &Matches) -> Option<Tool> {
+    match matches.subcommand() {
+        Some(("check-proof", sub_matches)) => {
+            let proof = sub_matches
+                .get_one::<String>("PROOF_FILE")
+                .expect("unreachable: `PROOF_FILE` argument is mandatory")
+                .clone();
+            let format = sub_matches
+                .get_one("FORMAT")
+                .cloned()
+                .unwrap_or_else(|| checker::Format::from_path(&proof));
+            Some(Tool::CheckProof { proof, format })
+        }
+        Some(("dump", sub_matches)) => {
+            let output = sub_matches.get_one::<String>("OUTPUT").cloned();
+            let lines = *sub_matches
+                .get_one("LINES")
+                .expect("arguments with default value cannot be absent");
+            Some(Tool::Dump { output, lines })
+        }
+        Some(("minimize", sub_matches)) => {
+            let output = sub_matches.get_one::<String>("OUTPUT").cloned();
+            Some(Tool::Minimize { output })
+        }
+        Some(("gen", sub_matches)) => gen_from_matches(sub_matches),
+        Some(("bench", sub_matches)) => {
+            let dir = sub_matches
+                .get_one::<String>("DIR")
+                .expect("unreachable: `DIR` argument is mandatory")
+                .clone();
+            let variants = match sub_matches.get_many::<DpllImpl>("VARIANTS") {
+                Some(variants) => variants.cloned().collect(),
+                None => DpllImpl::ALL.to_vec(),
+            };
+            let csv = sub_matches.get_one::<String>("CSV").cloned();
+            let json = sub_matches.get_one::<String>("JSON").cloned();
+            Some(Tool::Bench {
+                dir,
+                variants,
+                csv,
+                json,
+            })
+        }
+        _ => None,
+    }
+}
+
 pub type Conf1 = Conf<Res<Option<DpllImpl>>>;
 pub type Conf2 = Conf<Option<DpllImpl>>;
 
 pub struct Conf<D> {
     pub start: Instant,
-    pub file: String,
+    /// Input file, only `None` for tools that do not read it.
+    pub file: Option<String>,
     pub dpll: D,
+    /// Branching heuristic of the recursive variants.
+    pub branching: Branching,
+    /// True if the *pure* rule is active, ignored by the variants that do not support it.
+    pub pure: bool,
+    /// Restart policy of the CDCL variants.
+    pub restarts: Restarts,
+    /// Learned clause database settings of the CDCL variants.
+    pub reduction: Reduction,
+    /// True if the CNF is preprocessed before solving.
+    pub preprocess: bool,
     pub log_level: LevelFilter,
     pub timeout_ms: Option<u64>,
     pub expecting_sat: Option<bool>,
     pub check_models: bool,
+    pub print_model: bool,
+    pub exit_codes: bool,
+    pub header_check: HeaderCheck,
+    pub proof: Option<String>,
+    pub core: Option<String>,
+    pub tool: Option<Tool>,
 }
 impl Conf1 {
     fn validate_bool(s: &str) -> Result<bool, String> {
@@ -68,6 +453,47 @@ impl Conf1 {
             _ => Err(format!("expected `sat|unsat`, got `{}`", s)),
         }
     }
+    fn validate_proof_format(s: &str) -> Result<checker::Format, String> {
+        checker::Format::from_name(s).ok_or_else(|| format!("expected `drat|lrat`, got `{}`", s))
+    }
+    fn validate_u64(s: &str) -> Result<u64, String> {
+        match s.parse::<u64>() {
+            Ok(res) => Ok(res),
+            Err(_) => Err(format!("expected integer, got `{}`", s)),
+        }
+    }
+    fn validate_usize(s: &str) -> Result<usize, String> {
+        match s.parse::<usize>() {
+            Ok(res) => Ok(res),
+            Err(_) => Err(format!("expected integer, got `{}`", s)),
+        }
+    }
+    fn validate_ratio(s: &str) -> Result<f64, String> {
+        match s.parse::<f64>() {
+            Ok(res) if res.is_finite() && res >= 0. => Ok(res),
+            _ => Err(format!("expected non-negative float, got `{}`", s)),
+        }
+    }
+    fn validate_dpll_impl(s: &str) -> Result<DpllImpl, String> {
+        let (name, sub_name) = match s.split_once('/') {
+            Some((name, sub_name)) => (name, Some(sub_name)),
+            None => (s, None),
+        };
+        DpllImpl::from_name(name, sub_name)
+            .ok_or_else(|| format!("expected DPLL variant `<impl>[/<dpll>]`, got `{}`", s))
+    }
+    fn validate_branching(s: &str) -> Result<Branching, String> {
+        Branching::from_name(s).ok_or_else(|| {
+            let names: Vec<_> = Branching::NAMES.iter().map(|(name, _)| *name).collect();
+            format!("expected `{}`, got `{}`", names.join("|"), s)
+        })
+    }
+    fn validate_restarts(s: &str) -> Result<Restarts, String> {
+        Restarts::from_name(s).ok_or_else(|| {
+            let names: Vec<_> = Restarts::NAMES.iter().map(|(name, _)| *name).collect();
+            format!("expected `{}`, got `{}`", names.join("|"), s)
+        })
+    }
     fn validate_timeout(s: &str) -> Result<u64, String> {
         match u64::from_str_radix(&s, 10) {
             Ok(res) => Ok(res),
@@ -77,7 +503,7 @@ impl Conf1 {
 
     pub fn new() -> Self {
         use clap::{crate_authors, crate_description, crate_version, Arg};
-        let matches = Command::new("sat_micro")
+        let mut cmd = Command::new("sat_micro")
             .version(crate_version!())
             .author(crate_authors!())
             .about(crate_description!())
@@ -104,6 +530,145 @@ impl Conf1 {
                     .default_value("off")
                     .help("(De)activates model checking, [on|off|true|false]"),
             )
+            .arg(
+                Arg::new("MODEL")
+                    .value_name("on|true|off|false")
+                    .long("model")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_bool)
+                    .default_value("on")
+                    .help("(De)activates model output (`v` lines) on sat results, [on|off|true|false]"),
+            )
+            .arg(
+                Arg::new("EXIT_CODES")
+                    .value_name("on|true|off|false")
+                    .long("exit-codes")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_bool)
+                    .default_value("off")
+                    .help(
+                        "(De)activates SAT-comp exit codes: 10 for sat, 20 for unsat, 0 for \
+                        unknown, 2 on errors (including `--expect` mismatches), \
+                        [on|off|true|false]",
+                    ),
+            )
+            .arg(
+                Arg::new("STRICT")
+                    .value_name("on|true|off|false")
+                    .long("strict")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_bool)
+                    .default_value("off")
+                    .help(
+                        "(De)activates strict parsing: literals over undeclared variables and \
+                        clause count mismatches w.r.t. the `p cnf` header are errors instead of \
+                        warnings, [on|off|true|false]",
+                    ),
+            )
+            .arg(
+                Arg::new("PROOF")
+                    .value_name("FILE")
+                    .long("proof")
+                    .num_args(1)
+                    .help(
+                        "Writes a DRAT proof to <FILE> on unsat results, \
+                        requires a single DPLL variant that supports proofs",
+                    ),
+            )
+            .arg(
+                Arg::new("CORE")
+                    .value_name("FILE")
+                    .long("core")
+                    .num_args(1)
+                    .help(
+                        "Writes an unsat core to <FILE> in the SAT-comp format on unsat results, \
+                        requires a single DPLL variant that supports proofs",
+                    ),
+            )
+            .arg(
+                Arg::new("BRANCH")
+                    .value_name("HEURISTIC")
+                    .long("branch")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_branching)
+                    .default_value("first")
+                    .help(format!(
+                        "Branching heuristic of the recursive variants, ignored by the other ones \
+                        in `all` mode: {}",
+                        Branching::NAMES
+                            .iter()
+                            .map(|(name, desc)| format!("`{}` ({})", name, desc))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+            )
+            .arg(
+                Arg::new("PURE")
+                    .value_name("on|true|off|false")
+                    .long("pure")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_bool)
+                    .default_value("off")
+                    .help(
+                        "(De)activates the pure literal rule of the recursive and iterative \
+                        variants, ignored by the other ones in `all` mode, incompatible with \
+                        `--proof` and `--core`, [on|off|true|false]",
+                    ),
+            )
+            .arg(
+                Arg::new("RESTARTS")
+                    .value_name("POLICY")
+                    .long("restarts")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_restarts)
+                    .default_value("never")
+                    .help(format!(
+                        "Restart policy of the CDCL variants, ignored by the other ones in `all` \
+                        mode: {}",
+                        Restarts::NAMES
+                            .iter()
+                            .map(|(name, desc)| format!("`{}` ({})", name, desc))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+            )
+            .arg(
+                Arg::new("MAX_LEARNED")
+                    .value_name("INT")
+                    .long("max-learned")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_usize)
+                    .help(
+                        "Number of learned clauses triggering the first reduction of the learned \
+                        clause database of the CDCL variants, ignored by the other ones in `all` \
+                        mode; learned clauses are never deleted by default",
+                    ),
+            )
+            .arg(
+                Arg::new("GLUE")
+                    .value_name("INT")
+                    .long("glue")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_usize)
+                    .default_value("2")
+                    .help(
+                        "Learned clauses with an LBD lower than or equal to this value are never \
+                        deleted by reductions, see `--max-learned`",
+                    ),
+            )
+            .arg(
+                Arg::new("PREPROCESS")
+                    .value_name("on|true|off|false")
+                    .long("preprocess")
+                    .num_args(1)
+                    .value_parser(Conf1::validate_bool)
+                    .default_value("off")
+                    .help(
+                        "(De)activates CNF preprocessing (subsumption, self-subsuming resolution, \
+                        failed literal probing and bounded variable elimination), incompatible \
+                        with `--proof` and `--core`, [on|off|true|false]",
+                    ),
+            )
             .arg(
                 Arg::new("TIMEOUT")
                     .value_name("INT")
@@ -115,12 +680,16 @@ impl Conf1 {
             )
             .subcommands(dpll_impl_subcommands())
             .subcommand(Command::new("all").about("Runs all DPLL variants"))
-            .arg(
-                Arg::new("FILE")
-                    .required(true)
-                    .help("Input file (SAT-comp format)"),
-            )
-            .get_matches();
+            .subcommand(check_proof_subcommand())
+            .subcommand(dump_subcommand())
+            .subcommand(minimize_subcommand())
+            .subcommand(gen_subcommand())
+            .subcommand(bench_subcommand())
+            .arg(Arg::new("FILE").help(
+                "Input file (SAT-comp format), `-` for stdin, can be compressed with xz, gzip, \
+                bzip2 or zstd; mandatory unless running `gen` or `bench`",
+            ));
+        let matches = cmd.get_matches_mut();
 
         let log_level = match matches.get_occurrences::<()>("VERB").iter().count() {
             0 => log::LevelFilter::Warn,
@@ -133,19 +702,73 @@ impl Conf1 {
         let check_models = *matches
             .get_one("CHECK")
             .expect("arguments with default value cannot be absent");
+        let print_model = *matches
+            .get_one("MODEL")
+            .expect("arguments with default value cannot be absent");
+        let exit_codes = *matches
+            .get_one("EXIT_CODES")
+            .expect("arguments with default value cannot be absent");
+        let header_check = if *matches
+            .get_one("STRICT")
+            .expect("arguments with default value cannot be absent")
+        {
+            HeaderCheck::Strict
+        } else {
+            HeaderCheck::Lenient
+        };
+        let branching = *matches
+            .get_one("BRANCH")
+            .expect("arguments with default value cannot be absent");
+        let pure = *matches
+            .get_one("PURE")
+            .expect("arguments with default value cannot be absent");
+        let restarts = *matches
+            .get_one("RESTARTS")
+            .expect("arguments with default value cannot be absent");
+        let reduction = Reduction {
+            max: matches.get_one("MAX_LEARNED").cloned(),
+            glue: *matches
+                .get_one("GLUE")
+                .expect("arguments with default value cannot be absent"),
+        };
+        let preprocess = *matches
+            .get_one("PREPROCESS")
+            .expect("arguments with default value cannot be absent");
+        let proof = matches.get_one::<String>("PROOF").cloned();
+        let core = matches.get_one::<String>("CORE").cloned();
 
-        let dpll = dpll_impl_from_matches(&matches);
+        let tool = tool_from_matches(&matches);
+        let dpll = if tool.is_some() {
+            Ok(None)
+        } else {
+            dpll_impl_from_matches(&matches)
+        };
 
-        let file = matches
-            .get_one::<String>("FILE")
-            .expect("unreachable: `FILE` argument is mandatory")
-            .clone();
+        let file = matches.get_one::<String>("FILE").cloned();
+        if file.is_none() && tool.as_ref().is_none_or(Tool::reads_input) {
+            cmd.error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "the input file <FILE> is mandatory",
+            )
+            .exit()
+        }
 
         Self {
             start: Instant::now(),
             file,
             check_models,
+            print_model,
+            exit_codes,
+            header_check,
+            proof,
+            core,
+            tool,
             dpll,
+            branching,
+            pure,
+            restarts,
+            reduction,
+            preprocess,
             log_level,
             timeout_ms,
             expecting_sat,
@@ -157,24 +780,101 @@ impl Conf1 {
             file,
             start,
             dpll,
+            branching,
+            pure,
+            restarts,
+            reduction,
+            preprocess,
             log_level,
             timeout_ms,
             expecting_sat,
             check_models,
+            print_model,
+            exit_codes,
+            header_check,
+            proof,
+            core,
+            tool,
         } = self;
         let dpll = dpll?;
+        if tool.is_none() {
+            match dpll {
+                Some(dpll) if branching != Branching::First && !dpll.has_branching() => {
+                    bail!("`--branch` is not supported by {}", dpll)
+                }
+                Some(dpll) if pure && !dpll.has_pure() => {
+                    bail!("`--pure` is not supported by {}", dpll)
+                }
+                Some(dpll) if restarts != Restarts::Never && !dpll.has_restarts() => {
+                    bail!("`--restarts` is not supported by {}", dpll)
+                }
+                Some(dpll) if reduction.max.is_some() && !dpll.has_reduction() => {
+                    bail!("`--max-learned` is not supported by {}", dpll)
+                }
+                _ => (),
+            }
+            for (flag, _) in [("--proof", &proof), ("--core", &core)]
+                .iter()
+                .filter(|(_, file)| file.is_some())
+            {
+                match dpll {
+                    _ if pure => bail!("`{}` is not compatible with `--pure`", flag),
+                    _ if preprocess => bail!("`{}` is not compatible with `--preprocess`", flag),
+                    None => bail!("`{}` requires a single DPLL variant, not `all`", flag),
+                    Some(dpll) if !dpll.has_proofs() => {
+                        bail!("`{}` is not supported by {}", flag, dpll)
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
         Ok(Conf2 {
             file,
             start,
             dpll,
+            branching,
+            pure,
+            restarts,
+            reduction,
+            preprocess,
             log_level,
             timeout_ms,
             expecting_sat,
             check_models,
+            print_model,
+            exit_codes,
+            header_check,
+            proof,
+            core,
+            tool,
         })
     }
 }
 impl<D> Conf<D> {
+    /// Solver options for some DPLL variant, the options it does not support are left to their
+    /// default value.
+    pub fn options(&self, dpll: DpllImpl) -> Options {
+        let mut options = Options::new();
+        if dpll.has_branching() {
+            options.branching = self.branching
+        }
+        if dpll.has_pure() {
+            options.pure = self.pure
+        }
+        if dpll.has_restarts() {
+            options.restarts = self.restarts
+        }
+        if dpll.has_reduction() {
+            options.reduction = self.reduction
+        }
+        options
+    }
+    /// Input file, fails if there is none.
+    pub fn file(&self) -> Res<&str> {
+        self.file
+            .as_deref()
+            .ok_or_else(|| "no input file provided".into())
+    }
     pub fn time_left(&self) -> Option<Duration> {
         self.timeout_ms.clone().map(|millis| {
             let timeout = Duration::from_millis(millis);
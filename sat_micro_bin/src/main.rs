@@ -1,10 +1,31 @@
 use std::time::Instant;
 
-use sat_micro::{dpll, front, front::prelude::*};
+use sat_micro::{
+    checker, dpll, front,
+    front::{
+        parse::{HeaderCheck, ParsedCnf},
+        prelude::*,
+    },
+};
 
 use crate::conf::*;
 
+pub mod bench;
 pub mod conf;
+pub mod mem;
+pub mod minimize;
+
+#[global_allocator]
+static ALLOC: mem::Counting = mem::Counting;
+
+/// SAT-comp exit code for sat results.
+const SAT_EXIT_CODE: i32 = 10;
+/// SAT-comp exit code for unsat results.
+const UNSAT_EXIT_CODE: i32 = 20;
+/// SAT-comp exit code for unknown results.
+const UNKNOWN_EXIT_CODE: i32 = 0;
+/// Exit code for errors, including results not matching `--expect`.
+const ERROR_EXIT_CODE: i32 = 2;
 
 fn main() {
     let conf = Conf::new();
@@ -13,8 +34,18 @@ fn main() {
     simplelog::SimpleLogger::init(conf.log_level, simplelog::Config::default())
         .expect("fatal error during logger initialization");
 
+    let exit_codes = conf.exit_codes;
+
     match run(conf) {
-        Ok(()) => std::process::exit(0),
+        Ok(is_sat) => {
+            let code = match is_sat {
+                _ if !exit_codes => 0,
+                Some(true) => SAT_EXIT_CODE,
+                Some(false) => UNSAT_EXIT_CODE,
+                None => UNKNOWN_EXIT_CODE,
+            };
+            std::process::exit(code)
+        }
         Err(errors) => {
             eprintln!("|===| Error(s):");
             for (idx, error) in errors.iter().enumerate() {
@@ -34,76 +65,261 @@ fn main() {
                 }
             }
             eprintln!("|===|");
-            std::process::exit(2)
+            std::process::exit(ERROR_EXIT_CODE)
         }
     }
 }
 
-pub fn run(conf: Conf1) -> Result<(), Vec<err::Error>> {
+/// Runs the solver(s) on the input file, yields `None` if the result is unknown.
+pub fn run(conf: Conf1) -> Result<Option<bool>, Vec<err::Error>> {
     let conf = conf.extract_dpll().map_err(|e| vec![e])?;
 
-    let cnf_file_path = std::path::PathBuf::from(&conf.file);
-    let xz_compressed = match cnf_file_path.extension() {
-        Some(ext) if "cnf" == ext => false,
-        Some(ext) if "xz" == ext => true,
-        _ => {
-            return Err(vec![format!(
-                "could not retrieve extension from `{}`, expected `.cnf` or `.xz`",
-                cnf_file_path.display()
-            )
-            .into()])
-        }
-    };
+    if let Some(tool) = conf.tool.as_ref() {
+        return run_tool(&conf, tool).map(|()| None).map_err(|e| vec![e]);
+    }
 
     use front::parse::Parser;
 
-    let expecting_sat = conf.expecting_sat.clone();
+    let expecting_sat = conf.expecting_sat;
+    let print_model = conf.print_model;
 
     log::debug!("creating parser...");
-    let is_sat = if xz_compressed {
-        parse_run(
-            Parser::open_xz_file(cnf_file_path)
-                .chain_err(|| "while creating xz parser")
-                .map_err(|e| vec![e])?,
-            conf,
-        )?
-    } else {
-        parse_run(
-            Parser::open_file(cnf_file_path)
-                .chain_err(|| "while creating uncompressed parser")
-                .map_err(|e| vec![e])?,
-            conf,
-        )?
-    };
+    let parser = Parser::open(conf.file().map_err(|e| vec![e])?)
+        .chain_err(|| "while creating parser")
+        .map_err(|e| vec![e])?
+        .header_check(conf.header_check);
+    let outcome = parse_run(parser, conf)?;
 
     const SAT: &str = "SATISFIABLE";
     const UNSAT: &str = "UNSATISFIABLE";
     const UNK: &str = "UNKNOWN";
-    match is_sat {
-        Some(true) => {
+    match &outcome {
+        Some(dpll::Outcome::Sat(model)) => {
             println!("s {}", SAT);
-            match expecting_sat {
-                Some(false) => bail!(vec!["expected unsat result, got sat".into()]),
-                Some(true) | None => (),
+            if print_model {
+                self::print_model(model)
+            }
+            if let Some(false) = expecting_sat {
+                bail!(vec!["expected unsat result, got sat".into()])
             }
         }
-        Some(false) => {
+        Some(dpll::Outcome::Unsat(())) => {
             println!("s {}", UNSAT);
-            match expecting_sat {
-                Some(true) => bail!(vec!["expect sat result, got unsat".into()]),
-                Some(false) | None => (),
+            if let Some(true) = expecting_sat {
+                bail!(vec!["expect sat result, got unsat".into()])
             }
         }
-        None => println!("s {}", UNK),
+        Some(dpll::Outcome::Unknown) | None => println!("s {}", UNK),
     }
 
+    Ok(outcome.map(|outcome| outcome.is_sat()))
+}
+
+/// Parses a CNF file, `-` for stdin.
+fn parse_file(path: impl AsRef<std::path::Path>, header_check: HeaderCheck) -> Res<ParsedCnf> {
+    front::parse::Parser::open(path)
+        .chain_err(|| "while creating parser")?
+        .header_check(header_check)
+        .parse()
+}
+
+/// Runs a tool on the input file.
+fn run_tool(conf: &Conf2, tool: &Tool) -> Res<()> {
+    let header_check = conf.header_check;
+    match tool {
+        Tool::CheckProof { proof, format } => {
+            let cnf = parse_file(conf.file()?, header_check)?;
+            println!("c checking {} proof `{}`", format, proof);
+            let start = Instant::now();
+            let res = checker::check_file(&cnf, proof, *format);
+            let time = Instant::now() - start;
+            match res {
+                Ok(summary) => {
+                    println!(
+                        "c checked {} of {} lemma(s) in {} seconds",
+                        summary.checked,
+                        summary.lemmas,
+                        time.as_secs_f64()
+                    );
+                    if let Some(core) = summary.core {
+                        println!("c proof uses {} of {} clause(s)", core, cnf.len())
+                    }
+                    println!("s VERIFIED");
+                    Ok(())
+                }
+                Err(e) => {
+                    println!("s NOT VERIFIED");
+                    Err(e).chain_err(|| format!("while checking proof `{}`", proof))
+                }
+            }
+        }
+        Tool::Dump { output, lines } => {
+            let cnf = parse_file(conf.file()?, header_check)?;
+            match output {
+                None => dump(std::io::stdout().lock(), &cnf, *lines),
+                Some(path) => {
+                    let file = std::fs::File::create(path)
+                        .chain_err(|| format!("while creating output file `{}`", path))?;
+                    dump(std::io::BufWriter::new(file), &cnf, *lines)
+                        .chain_err(|| format!("while writing output file `{}`", path))
+                }
+            }
+        }
+        Tool::Minimize { output } => {
+            let file = conf.file()?;
+            let cnf = parse_file(file, header_check)?;
+            let deadline = conf.time_left().map(|time_left| Instant::now() + time_left);
+            let (clause_count, lit_count) = (cnf.len(), cnf.iter().map(|c| c.len()).sum::<usize>());
+            let minimized = minimize::minimize(cnf.into_cnf(), conf, deadline)?;
+            let comment = format!(
+                "minimized `{}` for: {}\n\
+                from {} clause(s) and {} literal(s) in {} test(s){}",
+                file,
+                minimized.failure,
+                clause_count,
+                lit_count,
+                minimized.tests,
+                if minimized.timeout {
+                    ", stopped early on timeout"
+                } else {
+                    ""
+                },
+            );
+            match output {
+                None => write_cnf(std::io::stdout().lock(), &comment, &minimized.cnf),
+                Some(path) => {
+                    let file = std::fs::File::create(path)
+                        .chain_err(|| format!("while creating output file `{}`", path))?;
+                    write_cnf(std::io::BufWriter::new(file), &comment, &minimized.cnf)
+                        .chain_err(|| format!("while writing output file `{}`", path))
+                }
+            }
+        }
+        Tool::Gen {
+            family,
+            seed,
+            count,
+            output,
+        } => gen(family, *seed, *count, output.as_deref()),
+        Tool::Bench {
+            dir,
+            variants,
+            csv,
+            json,
+        } => {
+            let records = bench::run(conf, dir, variants)?;
+            let create = |path: &str| {
+                std::fs::File::create(path)
+                    .map(std::io::BufWriter::new)
+                    .chain_err(|| format!("while creating output file `{}`", path))
+            };
+            if let Some(path) = csv {
+                bench::write_csv(create(path)?, &records)
+                    .chain_err(|| format!("while writing output file `{}`", path))?
+            }
+            if let Some(path) = json {
+                bench::write_json(create(path)?, &records)
+                    .chain_err(|| format!("while writing output file `{}`", path))?
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Generates `count` benchmarks with increasing seeds.
+///
+/// A single benchmark is written to `output`, or stdout if `None`. Several benchmarks are written
+/// to the `output` directory, one `<name>.cnf` file each.
+fn gen(family: &front::gen::Family, seed: u64, count: usize, output: Option<&str>) -> Res<()> {
+    if count > 1 && !family.is_random() {
+        bail!("cannot generate more than one instance of a non-random family")
+    }
+    match (count, output) {
+        (0, _) => Ok(()),
+        (1, None) => family.generate(seed)?.write(std::io::stdout().lock()),
+        (1, Some(path)) => {
+            let bench = family.generate(seed)?;
+            let file = std::fs::File::create(path)
+                .chain_err(|| format!("while creating output file `{}`", path))?;
+            bench
+                .write(std::io::BufWriter::new(file))
+                .chain_err(|| format!("while writing output file `{}`", path))
+        }
+        (_, None) => bail!("generating more than one instance requires an output directory"),
+        (_, Some(dir)) => {
+            std::fs::create_dir_all(dir)
+                .chain_err(|| format!("while creating output directory `{}`", dir))?;
+            for seed in (seed..).take(count) {
+                let bench = family.generate(seed)?;
+                let path = std::path::Path::new(dir).join(format!("{}.cnf", bench.name));
+                let file = std::fs::File::create(&path)
+                    .chain_err(|| format!("while creating output file `{}`", path.display()))?;
+                bench
+                    .write(std::io::BufWriter::new(file))
+                    .chain_err(|| format!("while writing output file `{}`", path.display()))?;
+                println!("c {} ({})", path.display(), bench.status())
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes a CNF in the SAT-comp format, preceded by a comment.
+fn write_cnf(w: impl std::io::Write, comment: &str, cnf: &Cnf<front::Lit>) -> Res<()> {
+    let mut w = front::write::Writer::new(w);
+    w.comment(comment)?;
+    w.cnf(cnf)?;
+    let _ = w.finish()?;
     Ok(())
 }
 
+/// Writes a CNF in the SAT-comp format, optionally preceding each clause with a comment giving
+/// its line in the input file.
+///
+/// The header declares all variables declared in the input file, even if they are unused.
+fn dump(w: impl std::io::Write, cnf: &ParsedCnf, lines: bool) -> Res<()> {
+    let mut w = front::write::Writer::new(w);
+    let var_count = front::write::var_count(cnf.iter()).max(cnf.var_count());
+    w.header(var_count, cnf.len())?;
+    for (idx, clause) in cnf.iter().enumerate() {
+        if let (true, Some(line)) = (lines, cnf.line_of(idx)) {
+            w.comment(format!("line {}", line))?
+        }
+        w.clause(clause.iter())?
+    }
+    let _ = w.finish()?;
+    Ok(())
+}
+
+/// Prints a model as SAT-comp `v` lines.
+fn print_model(model: &Set<front::Lit>) {
+    /// Maximum length of a `v` line.
+    const MAX_LEN: usize = 78;
+
+    let mut lits: Vec<_> = model.iter().collect();
+    lits.sort_by_key(|lit| lit.idx());
+
+    let mut line = String::from("v");
+    for lit in lits
+        .into_iter()
+        .map(|lit| lit.to_string())
+        .chain(Some("0".into()))
+    {
+        if line.len() + 1 + lit.len() > MAX_LEN {
+            println!("{}", line);
+            line.clear();
+            line.push('v');
+        }
+        line.push(' ');
+        line.push_str(&lit);
+    }
+    println!("{}", line)
+}
+
 pub fn parse_run<R: std::io::Read>(
     parser: front::parse::Parser<R>,
     conf: Conf2,
-) -> Result<Option<bool>, Vec<err::Error>> {
+) -> Result<Option<dpll::Outcome<front::Lit, ()>>, Vec<err::Error>> {
     let parse_start = Instant::now();
     log::debug!("running parser...");
     let cnf = parser.parse().map_err(|e| vec![e])?;
@@ -111,6 +327,14 @@ pub fn parse_run<R: std::io::Read>(
 
     let parse_time = parse_end - parse_start;
     println!("c done parsing in {} seconds", parse_time.as_secs_f64());
+    let unused = cnf.unused_vars().len();
+    if unused > 0 {
+        println!(
+            "c {} of the {} declared variable(s) do not appear in any clause",
+            unused,
+            cnf.var_count()
+        )
+    }
 
     log::debug!("parsed {} conjunct(s)", cnf.len());
     if log::log_enabled!(log::Level::Trace) {
@@ -119,73 +343,77 @@ pub fn parse_run<R: std::io::Read>(
         }
     }
 
-    if let Some(timeout) = conf.time_left() {
-        use std::sync::mpsc;
-        let (sender, recver) = mpsc::channel();
-        let _ = std::thread::spawn(move || {
-            let res = run_all(conf, cnf);
-            let _ = sender.send(res);
-        });
-        match recver.recv_timeout(timeout) {
-            Ok(res) => res,
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                println!("c TIMEOUT");
-                Ok(None)
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                bail!(vec!["unexpected deconnection from solver subprocess".into()])
-            }
-        }
-    } else {
-        run_all(conf, cnf)
+    let cancel = conf
+        .time_left()
+        .map_or_else(Cancel::new, Cancel::with_timeout);
+    let outcome = run_all(conf, cnf, &cancel)?;
+    if outcome.is_none() {
+        println!("c TIMEOUT")
     }
+    Ok(outcome)
 }
 
-fn run_all(conf: Conf2, cnf: Cnf<Lit>) -> Result<Option<bool>, Vec<err::Error>> {
+/// Runs the DPLL variant(s), yields `None` if they were all cancelled.
+fn run_all(
+    conf: Conf2,
+    cnf: ParsedCnf,
+    cancel: &Cancel,
+) -> Result<Option<dpll::Outcome<front::Lit, ()>>, Vec<err::Error>> {
+    let pre = if conf.preprocess {
+        let start = Instant::now();
+        let pre = dpll::preprocess::preprocess(cnf.cnf().clone());
+        println!(
+            "c preprocessed {} clause(s) into {} in {} seconds\nc {}",
+            cnf.len(),
+            pre.cnf().len(),
+            start.elapsed().as_secs_f64(),
+            pre.stats(),
+        );
+        Some(pre)
+    } else {
+        None
+    };
+
     let results = match conf.dpll {
         Some(dpll) => {
             println!("c running {}", dpll);
-            let res = run_one(&conf, cnf, dpll).chain_err(|| format!("while running {}", dpll));
+            let res = run_one(&conf, &cnf, pre.as_ref(), dpll, cancel)
+                .chain_err(|| format!("while running {}", dpll));
             vec![res]
         }
         None => {
-            let all = [
-                DpllImpl::Recursive(Dpll::Plain),
-                DpllImpl::Recursive(Dpll::Backjump),
-                DpllImpl::Recursive(Dpll::Cdcl),
-            ];
-            for dpll in &all {
+            let all = DpllImpl::ALL;
+            for dpll in all {
                 println!("c running {}", dpll);
             }
             if log::log_enabled!(log::Level::Info) {
                 log::info!("running the following dpll variants:");
-                for dpll in &all {
+                for dpll in all {
                     log::info!("- {}", dpll)
                 }
             }
 
             use rayon::prelude::*;
             all.par_iter()
-                .map(|dpll| run_one(&conf, cnf.clone(), *dpll))
+                .map(|dpll| run_one(&conf, &cnf, pre.as_ref(), *dpll, cancel))
                 .collect()
         }
     };
 
-    let mut is_sat = None;
+    let mut outcome: Option<dpll::Outcome<front::Lit, ()>> = None;
     let mut errors = Vec::<err::Error>::new();
 
     for res in results {
-        let res = res.and_then(|this_outcome| {
-            let sat = this_outcome.map_ref(|m| sat_action(conf.check_models, m), unsat_action)?;
-            if is_sat.is_none() {
-                is_sat = Some(sat)
-            } else if is_sat != Some(sat) {
-                errors.push(format!("results do not agree on satisfiability").into())
-            }
-            Ok(())
-        });
         match res {
-            Ok(()) => (),
+            Ok(dpll::Outcome::Unknown) => (),
+            Ok(this_outcome) => match outcome.as_ref() {
+                None => outcome = Some(this_outcome),
+                Some(outcome) => {
+                    if outcome.is_sat() != this_outcome.is_sat() {
+                        errors.push("results do not agree on satisfiability".into())
+                    }
+                }
+            },
             Err(e) => errors.push(e),
         }
     }
@@ -194,43 +422,109 @@ fn run_all(conf: Conf2, cnf: Cnf<Lit>) -> Result<Option<bool>, Vec<err::Error>>
         return Err(errors);
     }
 
-    Ok(is_sat)
+    if conf.print_model {
+        if let Some(dpll::Outcome::Sat(model)) = outcome.as_mut() {
+            cnf.complete_model(model)
+        }
+    }
+
+    Ok(outcome)
 }
+/// Runs a DPLL variant on `cnf`, or on its preprocessed version `pre` if any.
 fn run_one(
     conf: &Conf2,
-    cnf: dpll::Cnf<front::Lit>,
+    cnf: &ParsedCnf,
+    pre: Option<&dpll::preprocess::Preprocessed<front::Lit>>,
     dpll: DpllImpl,
+    cancel: &Cancel,
 ) -> Res<dpll::Outcome<front::Lit, ()>> {
+    let mut proof = match conf.proof.as_ref() {
+        None => None,
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .chain_err(|| format!("while creating proof file `{}`", path))?;
+            Some(dpll::proof::Drat::new(std::io::BufWriter::new(file)))
+        }
+    };
+    let proof_ref = proof
+        .as_mut()
+        .map(|proof| proof as &mut dyn dpll::proof::Proof<front::Lit>);
+
+    let options = conf.options(dpll);
+    let input = pre.map_or_else(|| cnf.cnf().clone(), |pre| pre.cnf().clone());
     let start = Instant::now();
-    let res = dpll::solve(cnf, dpll)?;
+    let (res, stats) = match conf.core.as_ref() {
+        None => dpll::solve_with_proof(input, dpll, &options, proof_ref, cancel)?,
+        Some(path) => {
+            let (res, stats) = dpll::solve_with_core(input, dpll, &options, proof_ref, cancel)?;
+            if let dpll::Outcome::Unsat(core) = &res {
+                write_core(path, cnf, core)
+                    .chain_err(|| format!("while writing unsat core file `{}`", path))?
+            }
+            (res.into_unit_unsat(), stats)
+        }
+    };
     let end = Instant::now();
+    let res = match (res, pre) {
+        (dpll::Outcome::Sat(model), Some(pre)) => dpll::Outcome::Sat(pre.reconstruct(model)),
+        (res, _) => res,
+    };
+
+    if let (Some(proof), Some(path)) = (proof, conf.proof.as_ref()) {
+        proof
+            .finish()
+            .chain_err(|| format!("while writing proof file `{}`", path))?;
+    }
 
     log::info!("{} is done", dpll);
 
     let time = end - start;
 
+    // Single `println` so that the lines of parallel runs do not interleave.
     println!(
-        "c {: >40} | {: ^5} | {: >15.9} seconds",
+        "c {: >40} | {: ^7} | {: >15.9} seconds\nc {: >40} | {}",
         dpll.to_string(),
-        if res.map_ref(|m| sat_action(conf.check_models, m), unsat_action)? {
-            "sat"
-        } else {
-            "unsat"
-        },
-        time.as_secs_f64()
+        res.map_ref(
+            |m| sat_action(conf.check_models, cnf, m).map(|_| "sat"),
+            |u| unsat_action(u).map(|_| "unsat"),
+            || Ok("unknown"),
+        )?,
+        time.as_secs_f64(),
+        "",
+        stats,
     );
 
     Ok(res)
 }
-fn sat_action(check_models: bool, _model: &Set<front::Lit>) -> Res<bool> {
-    // println!("s SATISFIABLE");
-    // for lit in &_model {
-    //     println!("    {}", lit)
-    // }
+/// Writes an unsat core in the SAT-comp format, each clause is preceded by a comment giving its
+/// line in the input file.
+fn write_core(path: &str, cnf: &ParsedCnf, core: &[usize]) -> Res<()> {
+    let file = std::fs::File::create(path)?;
+    let mut w = front::write::Writer::new(std::io::BufWriter::new(file));
+    w.comment(format!(
+        "unsat core, {} of {} clause(s)",
+        core.len(),
+        cnf.len()
+    ))?;
+    // Lenient mode accepts variables beyond the declared count.
+    let core_clauses = core.iter().map(|idx| &cnf[*idx]);
+    let var_count = front::write::var_count(core_clauses).max(cnf.var_count());
+    w.header(var_count, core.len())?;
+    for idx in core {
+        if let Some(line) = cnf.line_of(*idx) {
+            w.comment(format!("line {}", line))?
+        }
+        w.clause(cnf[*idx].iter())?
+    }
+    let _ = w.finish()?;
+    Ok(())
+}
+
+fn sat_action(check_models: bool, cnf: &ParsedCnf, model: &Set<front::Lit>) -> Res<bool> {
     if check_models {
-        for lit in _model {
+        for lit in model {
             let nlit = lit.ref_negate();
-            if _model.contains(&nlit) {
+            if model.contains(&nlit) {
                 return Err(format!(
                     "[fatal] inconsistent model contains both {} and {}",
                     lit, nlit
@@ -238,10 +532,11 @@ fn sat_action(check_models: bool, _model: &Set<front::Lit>) -> Res<bool> {
                 .into());
             }
         }
+        cnf.check_model(model)
+            .chain_err(|| "[fatal] model check failed")?;
     }
     Ok(true)
 }
 fn unsat_action(_: &()) -> Res<bool> {
-    // println!("s UNSATISFIABLE");
     Ok(false)
 }
@@ -0,0 +1,259 @@
+//! Delta debugging, shrinks a CNF while the DPLL variants keep misbehaving on it.
+//!
+//! The minimizer first looks for the [`Failure`] the input triggers, and then only keeps the
+//! reductions that trigger the same failure. It alternates between removing chunks of clauses
+//! (*ddmin*-style, with decreasing chunk sizes) and removing single literals, until it reaches a
+//! fixpoint or runs out of time.
+//!
+//! Variants run with the solver options of the configuration, see [`Conf2::options`], and on the
+//! preprocessed CNF if preprocessing is active, so that failures depending on them can be
+//! minimized too.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::Instant,
+};
+
+use rayon::prelude::*;
+use sat_micro::{dpll, front::prelude::*};
+
+use crate::conf::Conf2;
+
+/// Misbehaviors of the DPLL variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Variants do not agree on satisfiability.
+    Disagreement,
+    /// A variant panicked.
+    Crash,
+    /// A variant produced a model that is inconsistent or falsifies a clause.
+    BadModel,
+    /// A variant returned an error.
+    Error,
+}
+implem! {
+    for Failure {
+        Display {
+            |&self, fmt| match self {
+                Self::Disagreement => "results do not agree on satisfiability".fmt(fmt),
+                Self::Crash => "solver crash".fmt(fmt),
+                Self::BadModel => "model check failure".fmt(fmt),
+                Self::Error => "solver error".fmt(fmt),
+            }
+        }
+    }
+}
+
+/// Runs all DPLL variants on a CNF, yields the failure they trigger if any.
+///
+/// If several variants fail, the failure of the first one in [`DpllImpl::ALL`] is reported. Variants
+/// cancelled by `cancel` do not take part in disagreements.
+pub fn failure(cnf: &Cnf<Lit>, conf: &Conf2, cancel: &Cancel) -> Option<Failure> {
+    let results: Vec<Result<Option<bool>, Failure>> = DpllImpl::ALL
+        .par_iter()
+        .map(|dpll| run(cnf, *dpll, conf, cancel))
+        .collect();
+    let mut is_sat = None;
+    for res in &results {
+        match res {
+            Err(failure) => return Some(*failure),
+            Ok(None) => (),
+            Ok(Some(sat)) => {
+                if is_sat.get_or_insert(*sat) != sat {
+                    return Some(Failure::Disagreement);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Runs a DPLL variant on a CNF, yields `true` if the result is sat and `None` if it is cancelled.
+///
+/// Models are reconstructed if `conf` preprocesses the CNF, and then checked against `cnf`.
+fn run(
+    cnf: &Cnf<Lit>,
+    dpll: DpllImpl,
+    conf: &Conf2,
+    cancel: &Cancel,
+) -> Result<Option<bool>, Failure> {
+    let options = conf.options(dpll);
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        if conf.preprocess {
+            let pre = dpll::preprocess::preprocess(cnf.clone());
+            dpll::solve(pre.cnf().clone(), dpll, &options, cancel).map(|(res, stats)| {
+                let res = match res {
+                    dpll::Outcome::Sat(model) => dpll::Outcome::Sat(pre.reconstruct(model)),
+                    res => res,
+                };
+                (res, stats)
+            })
+        } else {
+            dpll::solve(cnf.clone(), dpll, &options, cancel)
+        }
+    })) {
+        Err(_) => Err(Failure::Crash),
+        Ok(Err(_)) => Err(Failure::Error),
+        Ok(Ok((dpll::Outcome::Unknown, _))) => Ok(None),
+        Ok(Ok((dpll::Outcome::Unsat(()), _))) => Ok(Some(false)),
+        Ok(Ok((dpll::Outcome::Sat(model), _))) => {
+            let inconsistent = model.iter().any(|lit| model.contains(&lit.ref_negate()));
+            if inconsistent || cnf.first_falsified(&model).is_some() {
+                Err(Failure::BadModel)
+            } else {
+                Ok(Some(true))
+            }
+        }
+    }
+}
+
+/// Result of a minimization.
+pub struct Minimized {
+    /// Smallest CNF found that triggers the failure.
+    pub cnf: Cnf<Lit>,
+    /// Failure `cnf` triggers.
+    pub failure: Failure,
+    /// Number of candidate CNFs tested.
+    pub tests: usize,
+    /// True if minimization stopped because of the deadline, in which case `cnf` might not be
+    /// minimal.
+    pub timeout: bool,
+}
+
+/// Minimizes a CNF while it triggers the same failure.
+///
+/// Fails if `cnf` does not trigger any failure. Panic messages are silenced while minimizing.
+pub fn minimize(cnf: Cnf<Lit>, conf: &Conf2, deadline: Option<Instant>) -> Res<Minimized> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| ()));
+    let res = Minimizer::new(cnf, conf, deadline).and_then(Minimizer::run);
+    panic::set_hook(hook);
+    res
+}
+
+struct Minimizer<'conf> {
+    clauses: Vec<Clause<Lit>>,
+    failure: Failure,
+    tests: usize,
+    /// Gives the solver options of the runs.
+    conf: &'conf Conf2,
+    /// Cancelled at the deadline, also interrupts the solvers.
+    cancel: Cancel,
+    timeout: bool,
+}
+impl<'conf> Minimizer<'conf> {
+    fn new(cnf: Cnf<Lit>, conf: &'conf Conf2, deadline: Option<Instant>) -> Res<Self> {
+        let cancel = deadline.map_or_else(Cancel::new, Cancel::with_deadline);
+        let failure = match failure(&cnf, conf, &cancel) {
+            Some(failure) => failure,
+            None => bail!("the input does not trigger any failure, nothing to minimize"),
+        };
+        log::info!("input triggers failure: {}", failure);
+        Ok(Self {
+            clauses: cnf.into_iter().collect(),
+            failure,
+            tests: 1,
+            conf,
+            cancel,
+            timeout: false,
+        })
+    }
+
+    /// True if the deadline is reached.
+    fn timed_out(&mut self) -> bool {
+        if !self.timeout {
+            self.timeout = self.cancel.is_cancelled();
+        }
+        self.timeout
+    }
+
+    /// True if some clauses trigger the failure we are minimizing for.
+    fn test(&mut self, clauses: &[Clause<Lit>]) -> bool {
+        self.tests += 1;
+        failure(&Cnf::new(clauses.to_vec()), self.conf, &self.cancel) == Some(self.failure)
+    }
+
+    fn run(mut self) -> Res<Minimized> {
+        while !self.timed_out() {
+            let clause_count = self.clauses.len();
+            let lit_count = self.lit_count();
+            self.remove_clauses();
+            self.remove_lits();
+            log::info!(
+                "{} clause(s), {} literal(s) after {} test(s)",
+                self.clauses.len(),
+                self.lit_count(),
+                self.tests
+            );
+            if clause_count == self.clauses.len() && lit_count == self.lit_count() {
+                break;
+            }
+        }
+        Ok(Minimized {
+            cnf: Cnf::new(self.clauses),
+            failure: self.failure,
+            tests: self.tests,
+            timeout: self.timeout,
+        })
+    }
+
+    fn lit_count(&self) -> usize {
+        self.clauses.iter().map(Clause::len).sum()
+    }
+
+    /// Removes chunks of clauses, starting with halves of the CNF and refining the chunks until
+    /// they are single clauses.
+    fn remove_clauses(&mut self) {
+        let mut chunks = 2;
+        while !self.clauses.is_empty() && !self.timed_out() {
+            let len = self.clauses.len();
+            let chunk_len = len.div_ceil(chunks);
+            let mut reduced = false;
+            let mut start = 0;
+            while start < len && !self.timed_out() {
+                let end = (start + chunk_len).min(len);
+                let candidate: Vec<_> = self.clauses[..start]
+                    .iter()
+                    .chain(&self.clauses[end..])
+                    .cloned()
+                    .collect();
+                if self.test(&candidate) {
+                    self.clauses = candidate;
+                    reduced = true;
+                    break;
+                }
+                start = end;
+            }
+            if reduced {
+                chunks = (chunks - 1).max(2)
+            } else if chunk_len == 1 {
+                break;
+            } else {
+                chunks = (chunks * 2).min(len)
+            }
+        }
+    }
+
+    /// Removes single literals from the clauses.
+    fn remove_lits(&mut self) {
+        for clause_idx in 0..self.clauses.len() {
+            let mut lit_idx = 0;
+            while lit_idx < self.clauses[clause_idx].len() && !self.timed_out() {
+                let mut candidate = self.clauses.clone();
+                candidate[clause_idx] = Clause::new(
+                    self.clauses[clause_idx]
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| *idx != lit_idx)
+                        .map(|(_, lit)| *lit)
+                        .collect(),
+                );
+                if self.test(&candidate) {
+                    self.clauses = candidate
+                } else {
+                    lit_idx += 1
+                }
+            }
+        }
+    }
+}
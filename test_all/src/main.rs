@@ -1,6 +1,13 @@
+//! Runs `sat_micro_bin` on a set of benchmarks, for each DPLL variant, and reports a summary.
+//!
+//! Benchmarks come from a manifest, a directory, or are generated locally with the `gen`
+//! subcommand of the solver, so that no network access is needed.
+
 use std::{
+    io::BufRead,
     path::{Path, PathBuf},
     process::{Command, Stdio},
+    time::{Duration, Instant},
 };
 
 use err::*;
@@ -18,45 +25,31 @@ mod err {
     }
 }
 
-const SAT_TAR_URL: &str =
-    "https://www.cs.ubc.ca/~hoos/SATLIB/Benchmarks/SAT/RND3SAT/uf50-218.tar.gz";
-const UNSAT_TAR_URL: &str =
-    "https://www.cs.ubc.ca/~hoos/SATLIB/Benchmarks/SAT/RND3SAT/uuf50-218.tar.gz";
-
-const TARGET_PATH: &str = "target/rsc";
-const SAT_DL_SUBDIR: &str = "sat";
-const UNSAT_DL_SUBDIR: &str = "unsat";
-const SAT_SUBDIR: &str = "sat";
-const UNSAT_SUBDIR: &str = "unsat/UUF50.218.1000";
-
-const SAT_MICRO_BIN: &str = "./target/debug/sat_micro_bin";
-fn sat_micro_cmd() -> Command {
-    Command::new(SAT_MICRO_BIN)
-}
-
-fn target() -> PathBuf {
-    PathBuf::from(TARGET_PATH)
-}
-fn sat_dl_target() -> PathBuf {
-    let mut path = target();
-    path.push(SAT_DL_SUBDIR);
-    path
-}
-fn unsat_dl_target() -> PathBuf {
-    let mut path = target();
-    path.push(UNSAT_DL_SUBDIR);
-    path
-}
-fn sat_target() -> PathBuf {
-    let mut path = target();
-    path.push(SAT_SUBDIR);
-    path
-}
-fn unsat_target() -> PathBuf {
-    let mut path = target();
-    path.push(UNSAT_SUBDIR);
-    path
-}
+const DEFAULT_SOLVER: &str = "./target/debug/sat_micro_bin";
+const DEFAULT_TIMEOUT_MS: &str = "10000";
+/// Directory where the default benchmarks are generated.
+const GEN_PATH: &str = "target/rsc/gen";
+/// DPLL variants, as `<implementation>/<dpll>` subcommands of the solver.
+const VARIANTS: &[&str] = &[
+    "recursive/plain",
+    "recursive/backjump",
+    "recursive/cdcl",
+    "iterative/plain",
+    "iterative/backjump",
+    "iterative/cdcl",
+    "imperative/cdcl",
+];
+/// Extensions of the files considered as benchmarks in directories.
+const EXTENSIONS: &[&str] = &["cnf", "xz", "gz", "bz2", "zst"];
+/// Time given to the solver to exit after its own timeout before it is killed.
+const GRACE: Duration = Duration::from_secs(1);
+
+/// SAT-comp exit code for sat results.
+const SAT_EXIT_CODE: i32 = 10;
+/// SAT-comp exit code for unsat results.
+const UNSAT_EXIT_CODE: i32 = 20;
+/// SAT-comp exit code for unknown results.
+const UNKNOWN_EXIT_CODE: i32 = 0;
 
 fn main() {
     match run() {
@@ -74,87 +67,328 @@ fn main() {
     }
 }
 
-fn run() -> Res<()> {
-    init()?;
-    build()?;
-    dl_untar()?;
-    run_solver()?;
-
-    Ok(())
+/// Expected status of a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Sat,
+    Unsat,
+    Unknown,
 }
-
-fn init() -> Res<()> {
-    fn create_dir_all(path: impl AsRef<Path>) -> Res<()> {
-        let path = path.as_ref();
-        std::fs::create_dir_all(path)
-            .chain_err(|| format!("while recursively creating `{}`", path.display()))
+impl Status {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sat" => Some(Self::Sat),
+            "unsat" => Some(Self::Unsat),
+            "unknown" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sat => "sat",
+            Self::Unsat => "unsat",
+            Self::Unknown => "unknown",
+        }
     }
-    simplelog::SimpleLogger::init(log::LevelFilter::Info, simplelog::Config::default())
-        .map_err(|e| format!("error during logger init: {}", e))?;
-    log::info!("creating target directories");
-    create_dir_all(sat_target())?;
-    create_dir_all(unsat_target())?;
-    Ok(())
 }
 
-fn dl_untar() -> Res<()> {
-    fn dl_untar_one(url: &str, path: impl AsRef<Path>) -> Res<()> {
-        let path = path.as_ref();
-        log::info!("downloading `{}`", url);
-        let bytes = reqwest::blocking::get(url)
-            .map_err(|e| format!("error getting `{}`:\n{}", url, e))?
-            .bytes()
-            .map_err(|e| format!("error downloading `{}`:\n{}", url, e))?;
-        log::info!("extracting to `{}`", path.display());
-        let decode = flate2::read::GzDecoder::new(&*bytes);
-        let mut archive = tar::Archive::new(decode);
-        archive
-            .unpack(path)
-            .map_err(|e| format!("error unpacking archive from `{}`:\n{}", url, e))?;
-        Ok(())
-    }
+/// A benchmark file and its expected status.
+struct Bench {
+    path: PathBuf,
+    expected: Status,
+}
 
-    let error_count = std::sync::RwLock::new(0usize);
+/// Outcome of a solver run on a benchmark.
+enum Outcome {
+    Pass,
+    Fail(String),
+    Timeout,
+}
 
-    use rayon::prelude::*;
+struct Conf {
+    /// Manifest or benchmark directory, `None` to generate the benchmarks.
+    benchmarks: Option<PathBuf>,
+    /// Solver command, program and arguments.
+    solver: Vec<String>,
+    timeout_ms: u64,
+    variants: Vec<String>,
+    build: bool,
+}
+impl Conf {
+    fn new() -> Self {
+        use clap::{value_parser, Arg};
+        let matches = clap::Command::new("test_all")
+            .about(
+                "Runs sat_micro on benchmarks for each DPLL variant and reports a summary of \
+                passes, failures and timeouts",
+            )
+            .arg(
+                Arg::new("BENCHMARKS")
+                    .value_parser(value_parser!(PathBuf))
+                    .help(
+                        "Either a manifest with one `<file> <sat|unsat|unknown>` entry per line, \
+                        or a directory; in directories, files in `sat`, `unsat` and `unknown` \
+                        sub-directories have the corresponding status, other files use their \
+                        `c expected: <status>` comment if any; if omitted, benchmarks are \
+                        generated in `target/rsc/gen` with the solver's `gen` subcommand",
+                    ),
+            )
+            .arg(
+                Arg::new("SOLVER")
+                    .long("solver")
+                    .takes_value(true)
+                    .default_value(DEFAULT_SOLVER)
+                    .help("Solver command, whitespace-separated program and arguments"),
+            )
+            .arg(
+                Arg::new("TIMEOUT")
+                    .long("timeout")
+                    .short('t')
+                    .takes_value(true)
+                    .value_name("MS")
+                    .value_parser(value_parser!(u64))
+                    .default_value(DEFAULT_TIMEOUT_MS)
+                    .help("Timeout in milliseconds for each run"),
+            )
+            .arg(
+                Arg::new("VARIANTS")
+                    .long("variants")
+                    .takes_value(true)
+                    .multiple_values(true)
+                    .value_name("IMPL/DPLL")
+                    .help("DPLL variants to run, all of them by default"),
+            )
+            .arg(
+                Arg::new("BUILD")
+                    .long("build")
+                    .takes_value(true)
+                    .value_parser(value_parser!(bool))
+                    .default_value("true")
+                    .help("Builds the solver with `cargo build` before running it"),
+            )
+            .get_matches();
+
+        let variants = match matches.get_many::<String>("VARIANTS") {
+            Some(variants) => variants.cloned().collect(),
+            None => VARIANTS.iter().map(|variant| variant.to_string()).collect(),
+        };
+        Self {
+            benchmarks: matches.get_one::<PathBuf>("BENCHMARKS").cloned(),
+            solver: matches
+                .get_one::<String>("SOLVER")
+                .expect("arguments with default value cannot be absent")
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            timeout_ms: *matches
+                .get_one("TIMEOUT")
+                .expect("arguments with default value cannot be absent"),
+            variants,
+            build: *matches
+                .get_one("BUILD")
+                .expect("arguments with default value cannot be absent"),
+        }
+    }
 
-    let todo: [fn() -> Res<()>; 2] = [
-        || dl_untar_one(SAT_TAR_URL, sat_dl_target()),
-        || dl_untar_one(UNSAT_TAR_URL, unsat_dl_target()),
-    ];
-    todo.par_iter()
-        .map(|run| match run() {
-            Ok(()) => (),
-            Err(e) => {
-                for (idx, e) in e.iter().enumerate() {
-                    let pref = if idx == 0 { "- " } else { "  " };
-                    eprintln!("{}{}", pref, e)
-                }
-                *error_count.write().expect("error lock was poisoned") += 1
+    fn solver_cmd(&self) -> Res<Command> {
+        match self.solver.split_first() {
+            Some((program, args)) => {
+                let mut cmd = Command::new(program);
+                cmd.args(args);
+                Ok(cmd)
             }
-        })
-        .collect::<()>();
+            None => bail!("empty solver command"),
+        }
+    }
+}
+
+fn run() -> Res<()> {
+    let conf = Conf::new();
+    init()?;
+    if conf.build {
+        build()?;
+    }
+    let benches = match conf.benchmarks.as_ref() {
+        Some(path) if path.is_dir() => from_dir(path)?,
+        Some(path) => from_manifest(path)?,
+        None => generate(&conf)?,
+    };
+    if benches.is_empty() {
+        bail!("no benchmarks to run")
+    }
+    run_solver(&conf, &benches)
+}
 
+fn init() -> Res<()> {
+    simplelog::SimpleLogger::init(log::LevelFilter::Info, simplelog::Config::default())
+        .map_err(|e| format!("error during logger init: {}", e))?;
     Ok(())
 }
 
 fn build() -> Res<()> {
     log::info!("building sat_micro (debug)");
-    std::process::Command::new("cargo")
+    let status = Command::new("cargo")
         .arg("build")
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .stdin(Stdio::null())
-        .spawn()
+        .status()
         .chain_err(|| "while building sat_micro with `cargo build`")?;
-
+    if !status.success() {
+        bail!("`cargo build` failed, run it manually for details")
+    }
     Ok(())
 }
 
-fn run_solver() -> Res<()> {
-    run_solver_on_dir(sat_target(), "sat")?;
-    run_solver_on_dir(unsat_target(), "unsat")?;
-    Ok(())
+/// Expected status given by a `c expected: <status>` comment before the header, if any.
+fn sniff_status(path: &Path) -> Res<Option<Status>> {
+    let file =
+        std::fs::File::open(path).chain_err(|| format!("while opening `{}`", path.display()))?;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.chain_err(|| format!("while reading `{}`", path.display()))?;
+        let line = line.trim();
+        match line.strip_prefix('c') {
+            None => break,
+            Some(comment) => {
+                if let Some(status) = comment.trim().strip_prefix("expected:") {
+                    return Ok(Status::from_name(status.trim()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Benchmarks from a directory, see the help of the `BENCHMARKS` argument.
+fn from_dir(dir: &Path) -> Res<Vec<Bench>> {
+    fn files(dir: &Path) -> Res<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in
+            std::fs::read_dir(dir).chain_err(|| format!("while opening `{}`", dir.display()))?
+        {
+            let path = entry
+                .chain_err(|| format!("while reading an entry of `{}`", dir.display()))?
+                .path();
+            let is_bench = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext));
+            if path.is_file() && is_bench {
+                files.push(path)
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    let mut benches = Vec::new();
+    for status in [Status::Sat, Status::Unsat, Status::Unknown] {
+        let sub_dir = dir.join(status.name());
+        if sub_dir.is_dir() {
+            for path in files(&sub_dir)? {
+                benches.push(Bench {
+                    path,
+                    expected: status,
+                })
+            }
+        }
+    }
+    for path in files(dir)? {
+        let expected = if path.extension().and_then(|ext| ext.to_str()) == Some("cnf") {
+            sniff_status(&path)?.unwrap_or(Status::Unknown)
+        } else {
+            Status::Unknown
+        };
+        benches.push(Bench { path, expected })
+    }
+    log::info!(
+        "found {} benchmark(s) in `{}`",
+        benches.len(),
+        dir.display()
+    );
+    Ok(benches)
+}
+
+/// Benchmarks from a manifest, paths are relative to the manifest's directory.
+///
+/// Empty lines and lines starting with `#` are ignored.
+fn from_manifest(manifest: &Path) -> Res<Vec<Bench>> {
+    let content = std::fs::read_to_string(manifest)
+        .chain_err(|| format!("while reading manifest `{}`", manifest.display()))?;
+    let dir = manifest.parent().unwrap_or_else(|| Path::new("."));
+    let mut benches = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = line
+            .rsplit_once(char::is_whitespace)
+            .and_then(|(file, status)| Some((file.trim_end(), Status::from_name(status)?)));
+        match entry {
+            Some((file, expected)) => benches.push(Bench {
+                path: dir.join(file),
+                expected,
+            }),
+            None => bail!(
+                "illegal entry `{}` at {}:{}, expected `<file> <sat|unsat|unknown>`",
+                line,
+                manifest.display(),
+                idx + 1
+            ),
+        }
+    }
+    log::info!(
+        "found {} benchmark(s) in manifest `{}`",
+        benches.len(),
+        manifest.display()
+    );
+    Ok(benches)
+}
+
+/// Generates the default benchmarks with the solver's `gen` subcommand.
+fn generate(conf: &Conf) -> Res<Vec<Bench>> {
+    let gen_dir = PathBuf::from(GEN_PATH);
+    log::info!("generating benchmarks in `{}`", gen_dir.display());
+    std::fs::create_dir_all(&gen_dir)
+        .chain_err(|| format!("while recursively creating `{}`", gen_dir.display()))?;
+
+    // `gen` arguments, and output file name for single instances (the output is the directory
+    // otherwise).
+    let todo: &[(&[&str], Option<&str>)] = &[
+        (
+            &["random", "--vars", "50", "--ratio", "4.26", "--count", "20"],
+            None,
+        ),
+        (&["parity", "--sat", "on", "--count", "10"], None),
+        (&["parity", "--sat", "off", "--count", "10"], None),
+        (&["coloring", "--planted", "on", "--count", "10"], None),
+        (&["pigeonhole", "--holes", "4"], Some("pigeonhole-4.cnf")),
+        (&["pigeonhole", "--holes", "5"], Some("pigeonhole-5.cnf")),
+        (&["queens", "--size", "3"], Some("queens-3.cnf")),
+        (&["queens", "--size", "6"], Some("queens-6.cnf")),
+        (&["queens", "--size", "8"], Some("queens-8.cnf")),
+    ];
+
+    for (args, file) in todo {
+        let output = match file {
+            Some(file) => gen_dir.join(file),
+            None => gen_dir.clone(),
+        };
+        let mut cmd = conf.solver_cmd()?;
+        cmd.arg("gen").args(*args).arg("-o").arg(&output);
+        cmd.stdout(Stdio::null());
+        cmd.stderr(Stdio::null());
+        cmd.stdin(Stdio::null());
+        let status = cmd
+            .status()
+            .chain_err(|| format!("while running `{:?}`", cmd))?;
+        if !status.success() {
+            bail!("benchmark generation failed on `{:?}`", cmd)
+        }
+    }
+
+    from_dir(&gen_dir)
 }
 
 fn progress_bar(n: usize) -> Res<indicatif::ProgressBar> {
@@ -169,118 +403,156 @@ fn progress_bar(n: usize) -> Res<indicatif::ProgressBar> {
     Ok(bar)
 }
 
-fn run_solver_on_dir(path: impl AsRef<Path>, expected: &str) -> Res<()> {
-    let path = path.as_ref();
-
+/// Runs all variants on all benchmarks and prints a summary, fails if any run failed.
+fn run_solver(conf: &Conf, benches: &[Bench]) -> Res<()> {
     log::info!(
-        "running sat_micro on all cnf-s in `{}`, expecting `{}`",
-        path.display(),
-        expected
+        "running {} variant(s) on {} benchmark(s) with a {}ms timeout",
+        conf.variants.len(),
+        benches.len(),
+        conf.timeout_ms
     );
 
-    let entries =
-        || std::fs::read_dir(path).chain_err(|| format!("while opening `{}`", path.display()));
-
-    let progress = progress_bar(entries()?.count())?;
-
-    let error_count = std::sync::RwLock::new(0usize);
+    let todo: Vec<(usize, usize)> = (0..conf.variants.len())
+        .flat_map(|variant| (0..benches.len()).map(move |bench| (variant, bench)))
+        .collect();
+    let progress = progress_bar(todo.len())?;
+    let fail_count = std::sync::RwLock::new(0usize);
 
     use rayon::prelude::*;
 
-    entries()?
-        .par_bridge()
-        .map(|entry| {
-            let res = entry
-                .as_ref()
-                .map_err(|e| {
-                    err::ErrorKind::from(format!(
-                        "error on an entry of `{}`: {}",
-                        path.display(),
-                        e
-                    ))
-                })
-                .and_then(|entry| run_solver_on(entry.path(), expected));
+    let outcomes: Vec<Outcome> = todo
+        .par_iter()
+        .map(|(variant, bench)| {
+            let outcome = run_solver_on(conf, &conf.variants[*variant], &benches[*bench])
+                .unwrap_or_else(|e| {
+                    Outcome::Fail(e.iter().fold(String::new(), |mut acc, e| {
+                        if !acc.is_empty() {
+                            acc.push_str(", ")
+                        }
+                        acc.push_str(&e.to_string());
+                        acc
+                    }))
+                });
             progress.inc(1);
-            match res {
-                Ok(()) => (),
-                Err(e) => {
-                    let file = entry
-                        .map(|e| e.path().display().to_string())
-                        .unwrap_or_else(|_| "<unknown file>".into());
-                    eprintln!(
-                        "error on `{}`:\n{}\n",
-                        file,
-                        e.iter().fold(format!("  "), |mut acc, e| {
-                            acc.push_str(&e.to_string());
-                            acc
-                        })
-                    );
-                    let mut error_count = error_count.write().expect("error lock is poisoned");
-                    *error_count += 1;
-                    progress.set_message(format!("got {} error(s)", error_count))
-                }
+            if let Outcome::Fail(_) = outcome {
+                let mut fail_count = fail_count.write().expect("fail lock is poisoned");
+                *fail_count += 1;
+                progress.set_message(format!("got {} failure(s)", fail_count))
             }
+            outcome
         })
-        .collect::<()>();
+        .collect();
 
     progress.finish();
 
-    let error_count = error_count.into_inner().expect("error lock is poisoned");
-    if error_count > 0 {
-        bail!("got {} error(s)", error_count)
-    } else {
+    let width = conf
+        .variants
+        .iter()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max("variant".len());
+    println!(
+        "| {:<width$} | {:>7} | {:>7} | {:>7} |",
+        "variant",
+        "pass",
+        "fail",
+        "timeout",
+        width = width
+    );
+    println!(
+        "|-{:-<width$}-|---------|---------|---------|",
+        "",
+        width = width
+    );
+    let mut failures = Vec::new();
+    for (variant_idx, variant) in conf.variants.iter().enumerate() {
+        let (mut pass, mut fail, mut timeout) = (0, 0, 0);
+        for ((this_variant, bench), outcome) in todo.iter().zip(&outcomes) {
+            if *this_variant != variant_idx {
+                continue;
+            }
+            match outcome {
+                Outcome::Pass => pass += 1,
+                Outcome::Timeout => timeout += 1,
+                Outcome::Fail(msg) => {
+                    fail += 1;
+                    failures.push(format!(
+                        "{} on `{}`: {}",
+                        variant,
+                        benches[*bench].path.display(),
+                        msg
+                    ))
+                }
+            }
+        }
+        println!(
+            "| {:<width$} | {:>7} | {:>7} | {:>7} |",
+            variant,
+            pass,
+            fail,
+            timeout,
+            width = width
+        );
+    }
+
+    if failures.is_empty() {
         Ok(())
+    } else {
+        println!();
+        for failure in &failures {
+            println!("- {}", failure)
+        }
+        bail!("got {} failure(s)", failures.len())
     }
 }
 
-fn run_solver_on(path: impl AsRef<Path>, expected: &str) -> Res<()> {
-    const TIMEOUT_MS: &str = "10000";
-    let path = path.as_ref();
-
-    if !path.exists() {
-        bail!("path `{}` does not exist", path.display())
-    } else if !path.is_file() {
-        bail!(
-            "path `{}` leads to a directory, not a (CNF) file",
-            path.display()
-        )
-    } else if path.extension() != Some(std::ffi::OsStr::new("cnf"))
-        && path.extension() != Some(std::ffi::OsStr::new("xz"))
-    {
-        bail!(
-            "path `{}` is not a legal CNF file, expected extension `cnf` or `xz",
-            path.display()
-        )
+/// Runs a variant on a benchmark, killing the solver if it runs past the timeout.
+fn run_solver_on(conf: &Conf, variant: &str, bench: &Bench) -> Res<Outcome> {
+    let path = &bench.path;
+    if !path.is_file() {
+        bail!("path `{}` does not lead to a (CNF) file", path.display())
     }
 
-    let cmd_str = || {
-        format!(
-            "{} --expect {} -t {} {} all",
-            SAT_MICRO_BIN,
-            expected,
-            TIMEOUT_MS,
-            path.display()
-        )
-    };
-    let mut cmd = sat_micro_cmd();
-    cmd.args(&["--expect", expected, "-t", TIMEOUT_MS]);
+    let mut cmd = conf.solver_cmd()?;
+    cmd.args(["--exit-codes", "on", "--check", "on", "--model", "off"]);
+    cmd.arg("-t").arg(conf.timeout_ms.to_string());
     cmd.arg(path);
-    cmd.arg("all");
+    cmd.args(variant.split('/'));
     cmd.stdout(Stdio::null());
     cmd.stderr(Stdio::null());
     cmd.stdin(Stdio::null());
 
-    let status = cmd
-        .status()
-        .chain_err(|| format!("while running `{}`", cmd_str()))?;
+    let deadline = Instant::now() + Duration::from_millis(conf.timeout_ms) + GRACE;
+    let mut child = cmd
+        .spawn()
+        .chain_err(|| format!("while running `{:?}`", cmd))?;
+    let status = loop {
+        match child.try_wait()? {
+            Some(status) => break status,
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(Outcome::Timeout);
+            }
+            None => std::thread::sleep(Duration::from_millis(10)),
+        }
+    };
 
-    if status.success() {
-        Ok(())
-    } else {
-        bail!(
-            "solver error on `{}` expecting `{}`",
-            path.display(),
-            expected
-        )
-    }
+    let is_sat = match status.code() {
+        Some(SAT_EXIT_CODE) => true,
+        Some(UNSAT_EXIT_CODE) => false,
+        Some(UNKNOWN_EXIT_CODE) => return Ok(Outcome::Timeout),
+        Some(code) => return Ok(Outcome::Fail(format!("solver error, exit code {}", code))),
+        None => return Ok(Outcome::Fail("solver killed by a signal".into())),
+    };
+    let outcome = match (bench.expected, is_sat) {
+        (Status::Unknown, _) | (Status::Sat, true) | (Status::Unsat, false) => Outcome::Pass,
+        (expected, is_sat) => Outcome::Fail(format!(
+            "expected {}, got {}",
+            expected.name(),
+            if is_sat { "sat" } else { "unsat" }
+        )),
+    };
+    Ok(outcome)
 }